@@ -0,0 +1,268 @@
+//! XML Catalogs (`xmlCatalog`)
+//!
+//! A catalog maps the public and system identifiers of DTDs and entities,
+//! and arbitrary URIs, to local copies, so documents that reference
+//! `http://...` resources can be processed offline. Catalogs follow the
+//! OASIS XML Catalogs format (SGML catalogs are accepted when loading).
+//!
+//! A `Catalog` is private to its owner: it is not installed as libxml2's
+//! process-wide catalog. Attach it to a parse with `ParserOptions::catalog`
+//! to resolve that parse's external DTDs and entities through it.
+//!
+//! ```no_run
+//! use libxml::catalog::Catalog;
+//! use libxml::parser::{Parser, ParserOptions};
+//!
+//! let catalog = Catalog::load("schemas/catalog.xml").unwrap();
+//! let options = ParserOptions {
+//!   dtd_load: true,
+//!   no_net: true,
+//!   catalog: Some(&catalog),
+//!   ..ParserOptions::default()
+//! };
+//! let doc = Parser::default()
+//!   .parse_file_with_options("book.xml", options)
+//!   .unwrap();
+//! ```
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::OnceLock;
+
+use crate::bindings::*;
+use crate::c_helpers::bindgenFree;
+
+/// Kind of entry added with `Catalog::add`, named after the catalog file elements
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CatalogEntry {
+  /// `<system systemId=orig uri=replace/>`
+  System,
+  /// `<public publicId=orig uri=replace/>`
+  Public,
+  /// `<rewriteSystem systemIdStartString=orig rewritePrefix=replace/>`
+  RewriteSystem,
+  /// `<delegatePublic publicIdStartString=orig catalog=replace/>`
+  DelegatePublic,
+  /// `<delegateSystem systemIdStartString=orig catalog=replace/>`
+  DelegateSystem,
+  /// `<uri name=orig uri=replace/>`
+  Uri,
+  /// `<rewriteURI uriStartString=orig rewritePrefix=replace/>`
+  RewriteUri,
+  /// `<delegateURI uriStartString=orig catalog=replace/>`
+  DelegateUri,
+  /// `<nextCatalog catalog=replace/>`, `orig` is ignored
+  NextCatalog,
+}
+
+impl CatalogEntry {
+  fn as_str(self) -> &'static str {
+    match self {
+      CatalogEntry::System => "system",
+      CatalogEntry::Public => "public",
+      CatalogEntry::RewriteSystem => "rewriteSystem",
+      CatalogEntry::DelegatePublic => "delegatePublic",
+      CatalogEntry::DelegateSystem => "delegateSystem",
+      CatalogEntry::Uri => "uri",
+      CatalogEntry::RewriteUri => "rewriteURI",
+      CatalogEntry::DelegateUri => "delegateURI",
+      CatalogEntry::NextCatalog => "nextCatalog",
+    }
+  }
+}
+
+/// A catalog loaded from a file, see the module documentation
+pub struct Catalog {
+  ptr: xmlCatalogPtr,
+}
+
+impl Drop for Catalog {
+  ///free the catalog when it goes out of scope
+  fn drop(&mut self) {
+    unsafe {
+      xmlFreeCatalog(self.ptr);
+    }
+  }
+}
+
+impl Catalog {
+  /// Load the catalog file at `path`.
+  ///
+  /// libxml2 only creates XML catalogs from a file; to build one
+  /// programmatically, load an empty `<catalog>` and `add` entries to it.
+  /// Relative URIs in the file are resolved against `path`.
+  pub fn load(path: &str) -> Result<Self, ()> {
+    crate::init_parser();
+    let c_path = CString::new(path).map_err(|_| ())?;
+    let ptr = unsafe { xmlLoadACatalog(c_path.as_ptr()) };
+    if ptr.is_null() {
+      return Err(());
+    }
+    // libxml2 only reads the entries of an XML catalog on first lookup;
+    // do it now so that `is_empty` reflects the file.
+    unsafe { bindgenFree(xmlACatalogResolveURI(ptr, c"".as_ptr() as *const xmlChar) as *mut c_void) };
+    Ok(Catalog { ptr })
+  }
+
+  /// Returns the raw libxml2 catalog pointer behind the struct
+  pub fn as_ptr(&self) -> xmlCatalogPtr {
+    self.ptr
+  }
+
+  /// Add an entry mapping `orig` to `replace`, replacing an entry of the same kind for `orig`
+  pub fn add(&mut self, entry: CatalogEntry, orig: &str, replace: &str) -> Result<(), ()> {
+    let c_type = CString::new(entry.as_str()).unwrap();
+    let c_orig = CString::new(orig).map_err(|_| ())?;
+    let c_replace = CString::new(replace).map_err(|_| ())?;
+    let status = unsafe {
+      xmlACatalogAdd(
+        self.ptr,
+        c_type.as_bytes().as_ptr(),
+        c_orig.as_bytes().as_ptr(),
+        c_replace.as_bytes().as_ptr(),
+      )
+    };
+    if status == 0 {
+      Ok(())
+    } else {
+      Err(())
+    }
+  }
+
+  /// Remove the entries for `value`
+  pub fn remove(&mut self, value: &str) -> Result<(), ()> {
+    let c_value = CString::new(value).map_err(|_| ())?;
+    if unsafe { xmlACatalogRemove(self.ptr, c_value.as_bytes().as_ptr()) } < 0 {
+      Err(())
+    } else {
+      Ok(())
+    }
+  }
+
+  /// Whether the catalog has no entries
+  pub fn is_empty(&self) -> bool {
+    unsafe { xmlCatalogIsEmpty(self.ptr) != 0 }
+  }
+
+  /// Resolve an external identifier, as for a `<!DOCTYPE>` or entity declaration
+  pub fn resolve(&self, public_id: Option<&str>, system_id: Option<&str>) -> Option<String> {
+    let c_public = public_id.map(CString::new).transpose().ok()?;
+    let c_system = system_id.map(CString::new).transpose().ok()?;
+    self.resolve_ptr(as_xml_ptr(&c_public), as_xml_ptr(&c_system))
+  }
+
+  /// Resolve a public identifier
+  pub fn resolve_public(&self, public_id: &str) -> Option<String> {
+    let c_public = CString::new(public_id).ok()?;
+    take_xml_string(unsafe { xmlACatalogResolvePublic(self.ptr, c_public.as_bytes().as_ptr()) })
+  }
+
+  /// Resolve a system identifier
+  pub fn resolve_system(&self, system_id: &str) -> Option<String> {
+    let c_system = CString::new(system_id).ok()?;
+    take_xml_string(unsafe { xmlACatalogResolveSystem(self.ptr, c_system.as_bytes().as_ptr()) })
+  }
+
+  /// Resolve a URI (`<uri>` entries), e.g. for stylesheets or schemas
+  pub fn resolve_uri(&self, uri: &str) -> Option<String> {
+    let c_uri = CString::new(uri).ok()?;
+    take_xml_string(unsafe { xmlACatalogResolveURI(self.ptr, c_uri.as_bytes().as_ptr()) })
+  }
+
+  /// Resolve an external identifier, falling back to the system identifier as a URI
+  fn resolve_ptr(&self, public_id: *const xmlChar, system_id: *const xmlChar) -> Option<String> {
+    if public_id.is_null() && system_id.is_null() {
+      return None;
+    }
+    take_xml_string(unsafe { xmlACatalogResolve(self.ptr, public_id, system_id) }).or_else(|| {
+      if system_id.is_null() {
+        None
+      } else {
+        take_xml_string(unsafe { xmlACatalogResolveURI(self.ptr, system_id) })
+      }
+    })
+  }
+}
+
+fn as_xml_ptr(value: &Option<CString>) -> *const xmlChar {
+  value
+    .as_ref()
+    .map_or(ptr::null(), |value| value.as_bytes().as_ptr())
+}
+
+/// Copy and free a string allocated by libxml2
+fn take_xml_string(value: *mut xmlChar) -> Option<String> {
+  if value.is_null() {
+    return None;
+  }
+  let string = unsafe { CStr::from_ptr(value as *const c_char) }
+    .to_string_lossy()
+    .into_owned();
+  bindgenFree(value as *mut c_void);
+  Some(string)
+}
+
+thread_local! {
+  /// Catalogs of the parses in progress on this thread, innermost last.
+  /// Only the innermost one applies; `None` for a parse without one.
+  static PARSE_CATALOGS: RefCell<Vec<Option<*const Catalog>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Makes a `ParserOptions::catalog` visible to the entity loader for the
+/// duration of one parse on the current thread.
+pub(crate) struct CatalogScope<'a> {
+  catalog: PhantomData<&'a Catalog>,
+}
+
+impl<'a> CatalogScope<'a> {
+  pub(crate) fn new(catalog: Option<&'a Catalog>) -> Self {
+    if catalog.is_some() {
+      install_entity_loader();
+    }
+    PARSE_CATALOGS.with(|catalogs| {
+      catalogs
+        .borrow_mut()
+        .push(catalog.map(|catalog| catalog as *const Catalog))
+    });
+    CatalogScope {
+      catalog: PhantomData,
+    }
+  }
+}
+
+impl Drop for CatalogScope<'_> {
+  fn drop(&mut self) {
+    PARSE_CATALOGS.with(|catalogs| catalogs.borrow_mut().pop());
+  }
+}
+
+/// The entity loader in place before ours, which loads the resolved resource
+static DEFAULT_LOADER: OnceLock<xmlExternalEntityLoader> = OnceLock::new();
+
+fn install_entity_loader() {
+  DEFAULT_LOADER.get_or_init(|| unsafe {
+    let default_loader = xmlGetExternalEntityLoader();
+    xmlSetExternalEntityLoader(Some(catalog_entity_loader));
+    default_loader
+  });
+}
+
+unsafe extern "C" fn catalog_entity_loader(
+  url: *const c_char,
+  id: *const c_char,
+  context: xmlParserCtxtPtr,
+) -> xmlParserInputPtr {
+  let catalog = PARSE_CATALOGS.with(|catalogs| catalogs.borrow().last().copied().flatten());
+  // SAFETY: the pointer is on the stack only while its `CatalogScope` borrows the catalog.
+  let resolved = catalog.and_then(|catalog| unsafe {
+    (*catalog).resolve_ptr(id as *const xmlChar, url as *const xmlChar)
+  });
+  let c_resolved = resolved.and_then(|resolved| CString::new(resolved).ok());
+  let url = c_resolved.as_ref().map_or(url, |resolved| resolved.as_ptr());
+  match DEFAULT_LOADER.get().copied().flatten() {
+    Some(default_loader) => unsafe { default_loader(url, id, context) },
+    None => ptr::null_mut(),
+  }
+}
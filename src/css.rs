@@ -0,0 +1,432 @@
+//! CSS selectors, translated to `XPath`
+//!
+//! Supports a CSS3 subset: type (`div`, `*`), `#id` and `.class` selectors,
+//! attribute selectors (`[a]`, `[a=v]`, `[a~=v]`, `[a|=v]`, `[a^=v]`, `[a$=v]`,
+//! `[a*=v]`), the descendant, child (`>`), adjacent (`+`) and general (`~`)
+//! sibling combinators, selector groups (`,`) and the `:first-child`,
+//! `:last-child`, `:only-child`, `:empty`, `:nth-child(an+b)`,
+//! `:nth-last-child(an+b)` and `:not(...)` pseudo-classes.
+//!
+//! Type selectors match the local name of elements in any namespace, and
+//! class selectors match one of the whitespace-separated names of the
+//! `class` attribute, as `Node::get_class_names` splits them. Attribute
+//! names must be XML names without a prefix. In HTML documents, type
+//! selectors and attribute names match case-insensitively, as the HTML
+//! parser stores names in lowercase.
+//!
+//! ```
+//! assert_eq!(
+//!   libxml::css::to_xpath("ul > li.active").unwrap(),
+//!   "descendant::*[local-name()='ul']/*[local-name()='li']\
+//!    [contains(concat(' ', normalize-space(@class), ' '), ' active ')]"
+//! );
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::bindings::{xmlElementType_XML_HTML_DOCUMENT_NODE, xmlNodePtr};
+use crate::c_helpers::xmlGetDoc;
+
+/// A selector that could not be parsed, or that uses unsupported syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorError {
+  /// byte offset in the selector where parsing failed
+  pub position: usize,
+  /// what went wrong
+  pub message: String,
+}
+
+impl fmt::Display for SelectorError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} at position {}", self.message, self.position)
+  }
+}
+
+impl Error for SelectorError {}
+
+/// Translate a CSS selector (group) into an `XPath` expression selecting the
+/// matching elements among the descendants of the context node
+pub fn to_xpath(selector: &str) -> Result<String, SelectorError> {
+  translate(selector, false)
+}
+
+/// Translate `selector` for the document of `node`, matching names in
+/// lowercase if it is an HTML document
+pub(crate) fn to_xpath_at(selector: &str, node: xmlNodePtr) -> Result<String, SelectorError> {
+  let doc = xmlGetDoc(node);
+  let html = !doc.is_null() && unsafe { (*doc).type_ } == xmlElementType_XML_HTML_DOCUMENT_NODE;
+  translate(selector, html)
+}
+
+/// A selector that parsed, but whose `XPath` translation could not be evaluated
+pub(crate) fn evaluation_error(selector: &str) -> SelectorError {
+  SelectorError {
+    position: selector.len(),
+    message: "the XPath translation could not be evaluated".to_owned(),
+  }
+}
+
+fn translate(selector: &str, html: bool) -> Result<String, SelectorError> {
+  let mut parser = SelectorParser {
+    input: selector,
+    chars: selector.char_indices().peekable(),
+    html,
+  };
+  let mut alternatives = vec![parser.selector()?];
+  while parser.eat(',') {
+    alternatives.push(parser.selector()?);
+  }
+  parser.skip_whitespace();
+  match parser.chars.peek() {
+    None => Ok(alternatives.join(" | ")),
+    Some(&(position, c)) => Err(SelectorError {
+      position,
+      message: format!("unexpected '{c}'"),
+    }),
+  }
+}
+
+struct SelectorParser<'a> {
+  input: &'a str,
+  chars: Peekable<CharIndices<'a>>,
+  /// whether names are matched in lowercase
+  html: bool,
+}
+
+impl SelectorParser<'_> {
+  fn position(&mut self) -> usize {
+    self
+      .chars
+      .peek()
+      .map_or(self.input.len(), |&(position, _)| position)
+  }
+
+  fn error<T>(&mut self, message: &str) -> Result<T, SelectorError> {
+    Err(SelectorError {
+      position: self.position(),
+      message: message.to_owned(),
+    })
+  }
+
+  fn skip_whitespace(&mut self) -> bool {
+    let mut skipped = false;
+    while self.chars.next_if(|&(_, c)| c.is_whitespace()).is_some() {
+      skipped = true;
+    }
+    skipped
+  }
+
+  /// Consume `expected`, and the whitespace around it, if it comes next
+  fn eat(&mut self, expected: char) -> bool {
+    self.skip_whitespace();
+    if self.chars.next_if(|&(_, c)| c == expected).is_some() {
+      self.skip_whitespace();
+      true
+    } else {
+      false
+    }
+  }
+
+  fn peek(&mut self) -> Option<char> {
+    self.chars.peek().map(|&(_, c)| c)
+  }
+
+  /// compound (combinator compound)*
+  fn selector(&mut self) -> Result<String, SelectorError> {
+    self.skip_whitespace();
+    let mut xpath = format!("descendant::*{}", self.compound()?);
+    loop {
+      let whitespace = self.skip_whitespace();
+      let step = match self.peek() {
+        Some(',') | None => return Ok(xpath),
+        Some('>') => "/*",
+        Some('+') => "/following-sibling::*[1]",
+        Some('~') => "/following-sibling::*",
+        Some(_) if whitespace => "/descendant::*",
+        Some(_) => return self.error("expected a combinator"),
+      };
+      if step != "/descendant::*" {
+        self.chars.next();
+        self.skip_whitespace();
+      }
+      xpath.push_str(step);
+      xpath.push_str(&self.compound()?);
+    }
+  }
+
+  /// A compound selector as `XPath` predicates
+  fn compound(&mut self) -> Result<String, SelectorError> {
+    let conditions = self.conditions()?;
+    Ok(
+      conditions
+        .iter()
+        .map(|condition| format!("[{condition}]"))
+        .collect(),
+    )
+  }
+
+  /// The conditions of a compound selector, e.g. `div.a#b` or `*:not(p)`
+  fn conditions(&mut self) -> Result<Vec<String>, SelectorError> {
+    let start = self.position();
+    let mut conditions = Vec::new();
+    if self.chars.next_if(|&(_, c)| c == '*').is_none() && self.peek().is_some_and(is_name_start) {
+      let name = self.name()?;
+      conditions.push(format!("local-name()={}", literal(&name)));
+    }
+    loop {
+      match self.peek() {
+        Some('#') => {
+          self.chars.next();
+          let id = self.identifier()?;
+          conditions.push(format!("@id={}", literal(&id)));
+        }
+        Some('.') => {
+          self.chars.next();
+          let class = self.identifier()?;
+          conditions.push(contains_word("@class", &class));
+        }
+        Some('[') => {
+          self.chars.next();
+          conditions.push(self.attribute()?);
+        }
+        Some(':') => {
+          self.chars.next();
+          conditions.push(self.pseudo_class()?);
+        }
+        _ => break,
+      }
+    }
+    if self.position() == start {
+      return self.error("expected a selector");
+    }
+    Ok(conditions)
+  }
+
+  fn attribute(&mut self) -> Result<String, SelectorError> {
+    self.skip_whitespace();
+    let start = self.position();
+    let name = self.name()?;
+    // the name is part of the XPath expression, not a string literal in it
+    if !is_ncname(&name) {
+      return Err(SelectorError {
+        position: start,
+        message: format!("invalid attribute name '{name}'"),
+      });
+    }
+    let name = format!("@{name}");
+    self.skip_whitespace();
+    let operator = match self.chars.next() {
+      Some((_, ']')) => return Ok(name),
+      Some((_, '=')) => '=',
+      Some((_, c)) if "~|^$*".contains(c) && self.chars.next_if(|&(_, c)| c == '=').is_some() => c,
+      _ => return self.error("expected an attribute operator"),
+    };
+    self.skip_whitespace();
+    let value = match self.peek() {
+      Some(quote @ ('"' | '\'')) => {
+        self.chars.next();
+        self.string(quote)?
+      }
+      _ => self.identifier()?,
+    };
+    self.skip_whitespace();
+    if self.chars.next_if(|&(_, c)| c == ']').is_none() {
+      return self.error("expected ']'");
+    }
+    let quoted = literal(&value);
+    Ok(match operator {
+      '=' => format!("{name}={quoted}"),
+      '~' => contains_word(&name, &value),
+      '|' => format!("{name}={quoted} or starts-with({name}, {})", literal(&format!("{value}-"))),
+      // an empty value matches nothing for the substring operators
+      _ if value.is_empty() => "false()".to_owned(),
+      '^' => format!("starts-with({name}, {quoted})"),
+      '$' => format!(
+        "substring({name}, string-length({name}) - {} + 1)={quoted}",
+        value.chars().count()
+      ),
+      _ => format!("contains({name}, {quoted})"),
+    })
+  }
+
+  fn pseudo_class(&mut self) -> Result<String, SelectorError> {
+    let name = self.identifier()?.to_ascii_lowercase();
+    let condition = match name.as_str() {
+      "first-child" => "not(preceding-sibling::*)".to_owned(),
+      "last-child" => "not(following-sibling::*)".to_owned(),
+      "only-child" => "not(preceding-sibling::*) and not(following-sibling::*)".to_owned(),
+      "empty" => "not(*) and not(text())".to_owned(),
+      "nth-child" | "nth-last-child" => {
+        let siblings = if name == "nth-child" {
+          "preceding-sibling::*"
+        } else {
+          "following-sibling::*"
+        };
+        self.open_argument()?;
+        let (a, b) = self.nth()?;
+        self.close_argument()?;
+        nth_condition(&format!("count({siblings}) + 1"), a, b)
+      }
+      "not" => {
+        self.open_argument()?;
+        let conditions = self.conditions()?;
+        self.close_argument()?;
+        if conditions.is_empty() {
+          "false()".to_owned()
+        } else {
+          let conditions: Vec<String> = conditions.iter().map(|c| format!("({c})")).collect();
+          format!("not({})", conditions.join(" and "))
+        }
+      }
+      _ => return self.error(&format!("unsupported pseudo-class :{name}")),
+    };
+    Ok(condition)
+  }
+
+  fn open_argument(&mut self) -> Result<(), SelectorError> {
+    if self.chars.next_if(|&(_, c)| c == '(').is_none() {
+      return self.error("expected '('");
+    }
+    self.skip_whitespace();
+    Ok(())
+  }
+
+  fn close_argument(&mut self) -> Result<(), SelectorError> {
+    self.skip_whitespace();
+    if self.chars.next_if(|&(_, c)| c == ')').is_none() {
+      return self.error("expected ')'");
+    }
+    Ok(())
+  }
+
+  /// The `an+b` argument of `:nth-child`, also `odd` and `even`
+  fn nth(&mut self) -> Result<(i64, i64), SelectorError> {
+    let start = self.position();
+    let mut argument = String::new();
+    while let Some((_, c)) = self.chars.next_if(|&(_, c)| c.is_ascii_alphanumeric() || "+- ".contains(c)) {
+      argument.push(c);
+    }
+    let argument: String = argument.split_whitespace().collect::<String>().to_ascii_lowercase();
+    let parsed = match argument.as_str() {
+      "odd" => Some((2, 1)),
+      "even" => Some((2, 0)),
+      _ => parse_nth(&argument),
+    };
+    parsed.ok_or(SelectorError {
+      position: start,
+      message: format!("invalid :nth-child argument '{argument}'"),
+    })
+  }
+
+  /// An element or attribute name, in lowercase for HTML documents
+  fn name(&mut self) -> Result<String, SelectorError> {
+    let name = self.identifier()?;
+    Ok(if self.html { name.to_lowercase() } else { name })
+  }
+
+  fn identifier(&mut self) -> Result<String, SelectorError> {
+    let mut name = String::new();
+    loop {
+      match self.peek() {
+        Some('\\') => {
+          self.chars.next();
+          match self.chars.next() {
+            Some((_, c)) => name.push(c),
+            None => return self.error("unfinished escape"),
+          }
+        }
+        Some(c) if is_name_start(c) || c.is_ascii_digit() || (c == '-' && !name.is_empty()) => {
+          self.chars.next();
+          name.push(c);
+        }
+        _ => break,
+      }
+    }
+    if name.is_empty() {
+      return self.error("expected a name");
+    }
+    Ok(name)
+  }
+
+  fn string(&mut self, quote: char) -> Result<String, SelectorError> {
+    let mut value = String::new();
+    loop {
+      match self.chars.next() {
+        Some((_, c)) if c == quote => return Ok(value),
+        Some((_, '\\')) => match self.chars.next() {
+          Some((_, c)) => value.push(c),
+          None => break,
+        },
+        Some((_, c)) => value.push(c),
+        None => break,
+      }
+    }
+    self.error("unterminated string")
+  }
+}
+
+fn is_name_start(c: char) -> bool {
+  c.is_ascii_alphabetic() || c == '_' || c == '-' || c == '\\' || !c.is_ascii()
+}
+
+/// Whether `name` is an XML name without a colon
+fn is_ncname(name: &str) -> bool {
+  let mut chars = name.chars();
+  chars
+    .next()
+    .is_some_and(|c| c == '_' || c.is_alphabetic())
+    && chars.all(|c| c.is_alphanumeric() || "-._\u{b7}".contains(c))
+}
+
+/// Parse `an+b` without whitespace: `3`, `n`, `-n+2`, `2n-1`, `+3n`
+fn parse_nth(argument: &str) -> Option<(i64, i64)> {
+  let Some(n_position) = argument.find('n') else {
+    return argument.parse().ok().map(|b| (0, b));
+  };
+  let a = match &argument[..n_position] {
+    "" | "+" => 1,
+    "-" => -1,
+    a => a.parse().ok()?,
+  };
+  let b = match &argument[n_position + 1..] {
+    "" => 0,
+    b if b.starts_with('+') || b.starts_with('-') => b.parse().ok()?,
+    _ => return None,
+  };
+  Some((a, b))
+}
+
+/// Whether the position `p` is `a*n + b` for some `n >= 0`
+fn nth_condition(p: &str, a: i64, b: i64) -> String {
+  match a {
+    0 => format!("{p} = {b}"),
+    a if a > 0 => format!("{p} >= {b} and ({p} - {b}) mod {a} = 0"),
+    a => format!("{p} <= {b} and ({b} - {p}) mod {} = 0", -a),
+  }
+}
+
+/// Whether the whitespace-separated list in `value` contains `word`
+fn contains_word(value: &str, word: &str) -> String {
+  if word.is_empty() || word.contains(char::is_whitespace) {
+    return "false()".to_owned();
+  }
+  format!(
+    "contains(concat(' ', normalize-space({value}), ' '), {})",
+    literal(&format!(" {word} "))
+  )
+}
+
+/// `value` as an `XPath` string literal
+fn literal(value: &str) -> String {
+  if !value.contains('\'') {
+    format!("'{value}'")
+  } else if !value.contains('"') {
+    format!("\"{value}\"")
+  } else {
+    let parts: Vec<String> = value.split('\'').map(|part| format!("'{part}'")).collect();
+    format!("concat({})", parts.join(", \"'\", "))
+  }
+}
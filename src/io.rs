@@ -29,8 +29,14 @@
 //!
 //! ## Lifetime, threading, order
 //!
-//! Closures live for the process lifetime — libxml2 has no per-handler
-//! unregister API. They may run on any thread (hence `Send + Sync`) and
+//! libxml2 has no per-handler unregister API, so a single trampoline
+//! entry delegates to a Rust registry. Closures registered with
+//! `register_input_callback` or `register_output_callback` live for the
+//! process lifetime; those from `register_scoped_input_callback` or
+//! `register_scoped_output_callback` until their guard is dropped. A
+//! resolver that should only serve one parse goes in
+//! `ParserOptions::resolver` instead, and is consulted before the
+//! registry. Closures may run on any thread (hence `Send + Sync`) and
 //! **must not panic**: unwinding across the `extern "C"` trampoline
 //! aborts on Rust 2024+. libxml2 walks callbacks newest-first; the
 //! trampolines snapshot the registry and drop the lock before invoking
@@ -38,15 +44,21 @@
 //! self-deadlock. If `open` returns `None`, libxml2 falls through to
 //! the next handler — including its default file/HTTP loaders.
 
+use std::cell::RefCell;
 use std::ffi::{CStr, c_char, c_int, c_void};
+use std::io::{Cursor, ErrorKind, Read};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 
-use crate::bindings::xmlRegisterInputCallbacks;
+use crate::bindings::{xmlRegisterInputCallbacks, xmlRegisterOutputCallbacks};
 
 type MatchFn = Box<dyn Fn(&str) -> bool + Send + Sync + 'static>;
-type OpenFn = Box<dyn Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static>;
+type OpenFn = Box<dyn Fn(&str) -> Option<InputReader> + Send + Sync + 'static>;
+type StoreFn = Box<dyn Fn(&str, Vec<u8>) -> std::io::Result<()> + Send + Sync + 'static>;
 
 struct Callback {
+  id:        u64,
   match_url: MatchFn,
   open:      OpenFn,
 }
@@ -74,7 +86,8 @@ fn snapshot() -> Vec<Arc<Callback>> {
 ///
 /// Closures are `Send + Sync + 'static` because libxml2 may invoke
 /// them from any thread. They are appended to a process-static
-/// registry; there is no per-handler unregister.
+/// registry for the rest of the process; use
+/// `register_scoped_input_callback` for a handler that can be removed.
 ///
 /// # Example
 ///
@@ -82,8 +95,9 @@ fn snapshot() -> Vec<Arc<Callback>> {
 /// libxslt, or RelaxNG `<include>` via `xmlRelaxNGParse`. Note that
 /// this crate's own `Parser::parse_file` uses Rust file I/O directly
 /// and bypasses libxml2's URL machinery, so it does *not* trigger
-/// these callbacks — wire them up via libxslt's `parse_bytes` or
-/// libxml2's `xmlReadFile`.
+/// these callbacks for the document itself — only for the external
+/// resources it references. Wire them up via libxslt's `parse_bytes`
+/// or libxml2's `xmlReadFile`.
 ///
 /// ```no_run
 /// use libxml::io;
@@ -100,11 +114,117 @@ where
   M: Fn(&str) -> bool + Send + Sync + 'static,
   O: Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
 {
+  add_input_callback(Box::new(match_url), bytes_opener(open));
+}
+
+/// Register a custom input callback whose `open` returns a reader
+/// instead of the whole resource.
+///
+/// Same as `register_input_callback` otherwise: libxml2 pulls bytes
+/// from the reader as it parses, so large or generated resources are
+/// never held in memory at once. A read error is reported to libxml2 as
+/// a failed read rather than as the end of the input, so the load fails
+/// instead of seeing a truncated resource; `ErrorKind::Interrupted` is
+/// retried.
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::Read;
+///
+/// use libxml::io;
+///
+/// io::register_input_reader(
+///   |url| url.starts_with("data:///"),
+///   |url| {
+///     let file = File::open(url.strip_prefix("data:///")?).ok()?;
+///     Some(Box::new(file) as Box<dyn Read + Send>)
+///   },
+/// );
+/// ```
+pub fn register_input_reader<M, O>(match_url: M, open: O)
+where
+  M: Fn(&str) -> bool + Send + Sync + 'static,
+  O: Fn(&str) -> Option<Box<dyn Read + Send>> + Send + Sync + 'static,
+{
+  add_input_callback(Box::new(match_url), Box::new(open));
+}
+
+/// Register a custom input callback with libxml2 until the returned
+/// guard is dropped.
+///
+/// Same as `register_input_callback` otherwise. Dropping the guard
+/// removes the handler from the registry; a load that already claimed
+/// a URL through it still completes.
+///
+/// ```no_run
+/// use libxml::io;
+///
+/// let guard = io::register_scoped_input_callback(
+///   |url| url.starts_with("tenant-a:///"),
+///   |_url| Some(b"<config/>".to_vec()),
+/// );
+/// // ... parse or transform for tenant A ...
+/// drop(guard);
+/// ```
+pub fn register_scoped_input_callback<M, O>(match_url: M, open: O) -> InputCallbackGuard
+where
+  M: Fn(&str) -> bool + Send + Sync + 'static,
+  O: Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+{
+  InputCallbackGuard {
+    id: add_input_callback(Box::new(match_url), bytes_opener(open)),
+  }
+}
+
+/// `register_input_reader` until the returned guard is dropped, see
+/// `register_scoped_input_callback`.
+pub fn register_scoped_input_reader<M, O>(match_url: M, open: O) -> InputCallbackGuard
+where
+  M: Fn(&str) -> bool + Send + Sync + 'static,
+  O: Fn(&str) -> Option<Box<dyn Read + Send>> + Send + Sync + 'static,
+{
+  InputCallbackGuard {
+    id: add_input_callback(Box::new(match_url), Box::new(open)),
+  }
+}
+
+/// Handle of an input callback registered with
+/// `register_scoped_input_callback`; unregisters it when dropped.
+#[must_use = "the input callback is unregistered as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct InputCallbackGuard {
+  id: u64,
+}
+
+impl Drop for InputCallbackGuard {
+  fn drop(&mut self) {
+    callbacks().lock().unwrap().retain(|cb| cb.id != self.id);
+  }
+}
+
+type InputReader = Box<dyn Read + Send>;
+
+/// Adapt a whole-resource `open` closure to the reader-based registry.
+fn bytes_opener<O>(open: O) -> OpenFn
+where
+  O: Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+{
+  Box::new(move |url| open(url).map(|bytes| Box::new(Cursor::new(bytes)) as InputReader))
+}
+
+fn add_input_callback(match_url: MatchFn, open: OpenFn) -> u64 {
+  static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+  let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
   callbacks().lock().unwrap().push(Arc::new(Callback {
-    match_url: Box::new(match_url),
-    open:      Box::new(open),
+    id,
+    match_url,
+    open,
   }));
+  register_input_trampolines();
+  id
+}
 
+fn register_input_trampolines() {
   // libxml2 records the trampoline pointers in a static table;
   // registering twice would push duplicate entries that delegate to
   // the same Rust registry. The OnceLock avoids that.
@@ -122,11 +242,99 @@ where
   });
 }
 
+/// A resolver for the external resources of one parse, see `ParserOptions::resolver`
+pub type InputResolver<'a> = dyn Fn(&str) -> Option<Vec<u8>> + 'a;
+
+type ResolveFn = InputResolver<'static>;
+
+thread_local! {
+  /// Resolvers of the parses in progress on this thread, innermost last.
+  /// Only the innermost one applies; `None` for a parse without one.
+  static PARSE_RESOLVERS: RefCell<Vec<Option<*const ResolveFn>>> = const { RefCell::new(Vec::new()) };
+  /// Bytes produced by a parse resolver in `trampoline_match`, for the
+  /// `trampoline_open` call that follows it.
+  static RESOLVED_INPUT: RefCell<Option<(String, Vec<u8>)>> = const { RefCell::new(None) };
+}
+
+/// Makes a `ParserOptions::resolver` visible to the trampolines for the
+/// duration of one parse on the current thread.
+pub(crate) struct ParseResolverScope<'a> {
+  resolver: PhantomData<&'a ResolveFn>,
+}
+
+impl<'a> ParseResolverScope<'a> {
+  pub(crate) fn new(resolver: Option<&'a InputResolver<'a>>) -> Self {
+    if resolver.is_some() {
+      register_input_trampolines();
+    }
+    // SAFETY: the pointer is only dereferenced while it is on the stack,
+    // and `drop` pops it before `'a` ends.
+    let resolver = resolver.map(|resolver| unsafe {
+      std::mem::transmute::<*const InputResolver<'a>, *const ResolveFn>(resolver)
+    });
+    PARSE_RESOLVERS.with(|resolvers| resolvers.borrow_mut().push(resolver));
+    ParseResolverScope {
+      resolver: PhantomData,
+    }
+  }
+}
+
+impl Drop for ParseResolverScope<'_> {
+  fn drop(&mut self) {
+    PARSE_RESOLVERS.with(|resolvers| resolvers.borrow_mut().pop());
+    RESOLVED_INPUT.with(|input| input.borrow_mut().take());
+  }
+}
+
+/// Ask the resolver of the innermost parse on this thread for `url`,
+/// keeping the bytes for `trampoline_open`.
+fn resolve_for_parse(url: &str) -> bool {
+  let resolver = PARSE_RESOLVERS.with(|resolvers| resolvers.borrow().last().copied().flatten());
+  let Some(resolver) = resolver else {
+    return false;
+  };
+  // SAFETY: see `ParseResolverScope::new`; the borrow is released before
+  // the call, so the resolver may itself parse.
+  match unsafe { (*resolver)(url) } {
+    Some(bytes) => {
+      RESOLVED_INPUT.with(|input| *input.borrow_mut() = Some((url.to_owned(), bytes)));
+      true
+    }
+    None => false,
+  }
+}
+
+fn take_resolved_input(url: &str) -> Option<Vec<u8>> {
+  RESOLVED_INPUT.with(|input| {
+    let mut input = input.borrow_mut();
+    match input.take() {
+      Some((resolved_url, bytes)) if resolved_url == url => Some(bytes),
+      other => {
+        *input = other;
+        None
+      }
+    }
+  })
+}
+
 /// Per-open state owned by libxml2 via `*mut c_void` until
 /// `trampoline_close` reclaims and drops it.
 struct OpenState {
-  bytes:    Vec<u8>,
-  position: usize,
+  reader: InputReader,
+  /// Initialized bytes `reader` reads into before they are copied to
+  /// libxml2's buffer, which `Read::read` must not see uninitialized.
+  /// Reused across reads, and only grown (and zeroed) when libxml2 asks
+  /// for more than before.
+  chunk:  Vec<u8>,
+}
+
+impl OpenState {
+  fn into_context(reader: InputReader) -> *mut c_void {
+    Box::into_raw(Box::new(OpenState {
+      reader,
+      chunk: Vec::new(),
+    })) as *mut c_void
+  }
 }
 
 unsafe extern "C" fn trampoline_match(filename: *const c_char) -> c_int {
@@ -139,7 +347,11 @@ unsafe extern "C" fn trampoline_match(filename: *const c_char) -> c_int {
     Ok(s) => s,
     Err(_) => return 0,
   };
-  // Newest-first, mirroring `trampoline_open`'s walk.
+  // The parse's own resolver first, then newest-first, mirroring
+  // `trampoline_open`'s walk.
+  if resolve_for_parse(url) {
+    return 1;
+  }
   for cb in snapshot().iter().rev() {
     if (cb.match_url)(url) {
       return 1;
@@ -157,13 +369,16 @@ unsafe extern "C" fn trampoline_open(filename: *const c_char) -> *mut c_void {
     Ok(s) => s,
     Err(_) => return std::ptr::null_mut(),
   };
+  if let Some(bytes) = take_resolved_input(url) {
+    return OpenState::into_context(Box::new(Cursor::new(bytes)));
+  }
   // Newest-first — the most recent registration wins.
   for cb in snapshot().iter().rev() {
     if !(cb.match_url)(url) {
       continue;
     }
-    if let Some(bytes) = (cb.open)(url) {
-      return Box::into_raw(Box::new(OpenState { bytes, position: 0 })) as *mut c_void;
+    if let Some(reader) = (cb.open)(url) {
+      return OpenState::into_context(reader);
     }
   }
   std::ptr::null_mut()
@@ -180,21 +395,24 @@ unsafe extern "C" fn trampoline_read(
   // SAFETY: `context` came from `Box::into_raw` in `trampoline_open`
   // and is not yet reclaimed; libxml2 holds one reference per handle.
   let state = unsafe { &mut *(context as *mut OpenState) };
-  let remaining = state.bytes.len().saturating_sub(state.position);
-  let n = remaining.min(len as usize);
-  if n == 0 {
-    return 0;
+  let len = len as usize;
+  if state.chunk.len() < len {
+    state.chunk.resize(len, 0);
   }
-  // SAFETY: bounds checked above; src and dst are disjoint allocations.
-  unsafe {
-    std::ptr::copy_nonoverlapping(
-      state.bytes.as_ptr().add(state.position),
-      buffer as *mut u8,
-      n,
-    );
+  let chunk = &mut state.chunk[..len];
+  loop {
+    match state.reader.read(chunk) {
+      Ok(n) => {
+        // a well-behaved `read` returns at most `chunk.len()`, which fits in a c_int
+        let n = n.min(len);
+        // SAFETY: libxml2 hands over `len` writable bytes at `buffer`.
+        unsafe { std::ptr::copy_nonoverlapping(chunk.as_ptr(), buffer as *mut u8, n) };
+        return n as c_int;
+      }
+      Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+      Err(_) => return -1,
+    }
   }
-  state.position += n;
-  n as c_int
 }
 
 unsafe extern "C" fn trampoline_close(context: *mut c_void) -> c_int {
@@ -206,6 +424,234 @@ unsafe extern "C" fn trampoline_close(context: *mut c_void) -> c_int {
   0
 }
 
+struct OutputCallback {
+  id:        u64,
+  match_url: MatchFn,
+  store:     StoreFn,
+}
+
+fn output_callbacks() -> &'static Mutex<Vec<Arc<OutputCallback>>> {
+  static CALLBACKS: OnceLock<Mutex<Vec<Arc<OutputCallback>>>> = OnceLock::new();
+  CALLBACKS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Same as `snapshot`, for the output registry.
+fn output_snapshot() -> Vec<Arc<OutputCallback>> {
+  output_callbacks().lock().unwrap().clone()
+}
+
+/// Register a custom output callback with libxml2.
+///
+/// `match_url` is consulted for every URL libxml2 opens for writing.
+/// Return `true` to claim the URL: libxml2's writes are then buffered
+/// in memory, and `store` receives the complete output together with
+/// the URL once the save finishes and libxml2 closes the output; it is
+/// never called with partial output. An `Err` from `store` makes the
+/// save fail. Unclaimed URLs fall through to the next registered
+/// handler, including libxml2's default file writer.
+///
+/// The lifetime, threading and ordering rules of
+/// `register_input_callback` apply: closures are `Send + Sync +
+/// 'static`, must not panic, and the newest matching registration
+/// wins. They are appended to a process-static registry for the rest
+/// of the process; use `register_scoped_output_callback` for a handler
+/// that can be removed.
+///
+/// `Document::save_file` and `Document::save_file_with_options` write
+/// through this chain, as does libxslt for `xsl:document`.
+///
+/// ```no_run
+/// use libxml::io;
+/// use libxml::parser::Parser;
+///
+/// io::register_output_callback(
+///   |url| url.starts_with("store:///"),
+///   |url, bytes| {
+///     println!("{url}: {} bytes", bytes.len());
+///     Ok(())
+///   },
+/// );
+///
+/// let doc = Parser::default().parse_string("<root/>").unwrap();
+/// doc.save_file("store:///root.xml").unwrap();
+/// ```
+pub fn register_output_callback<M, S>(match_url: M, store: S)
+where
+  M: Fn(&str) -> bool + Send + Sync + 'static,
+  S: Fn(&str, Vec<u8>) -> std::io::Result<()> + Send + Sync + 'static,
+{
+  add_output_callback(Box::new(match_url), Box::new(store));
+}
+
+/// Register a custom output callback with libxml2 until the returned
+/// guard is dropped.
+///
+/// Same as `register_output_callback` otherwise. Dropping the guard
+/// removes the handler from the registry; a save that already claimed
+/// a URL through it still hands its output to `store`.
+///
+/// ```no_run
+/// use libxml::io;
+///
+/// let guard = io::register_scoped_output_callback(
+///   |url| url.starts_with("tenant-a:///"),
+///   |_url, _bytes| Ok(()),
+/// );
+/// // ... save or transform for tenant A ...
+/// drop(guard);
+/// ```
+pub fn register_scoped_output_callback<M, S>(match_url: M, store: S) -> OutputCallbackGuard
+where
+  M: Fn(&str) -> bool + Send + Sync + 'static,
+  S: Fn(&str, Vec<u8>) -> std::io::Result<()> + Send + Sync + 'static,
+{
+  OutputCallbackGuard {
+    id: add_output_callback(Box::new(match_url), Box::new(store)),
+  }
+}
+
+/// Handle of an output callback registered with
+/// `register_scoped_output_callback`; unregisters it when dropped.
+#[must_use = "the output callback is unregistered as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct OutputCallbackGuard {
+  id: u64,
+}
+
+impl Drop for OutputCallbackGuard {
+  fn drop(&mut self) {
+    output_callbacks().lock().unwrap().retain(|cb| cb.id != self.id);
+  }
+}
+
+fn add_output_callback(match_url: MatchFn, store: StoreFn) -> u64 {
+  static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+  let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+  output_callbacks().lock().unwrap().push(Arc::new(OutputCallback {
+    id,
+    match_url,
+    store,
+  }));
+
+  // See `register_input_callback`: one libxml2 table entry delegates
+  // to the whole Rust registry.
+  static REGISTERED: OnceLock<()> = OnceLock::new();
+  REGISTERED.get_or_init(|| {
+    // also registers libxml2's default output handlers, which must
+    // precede ours in the table
+    crate::init_parser();
+    unsafe {
+      xmlRegisterOutputCallbacks(
+        Some(trampoline_output_match),
+        Some(trampoline_output_open),
+        Some(trampoline_output_write),
+        Some(trampoline_output_close),
+      );
+    }
+  });
+  id
+}
+
+/// Per-output state owned by libxml2 via `*mut c_void` until
+/// `trampoline_output_close` reclaims it and hands the bytes over.
+struct OutputState {
+  callback: Arc<OutputCallback>,
+  url:      String,
+  bytes:    Vec<u8>,
+}
+
+unsafe extern "C" fn trampoline_output_match(filename: *const c_char) -> c_int {
+  if filename.is_null() {
+    return 0;
+  }
+  // SAFETY: see `trampoline_match`.
+  let url = match unsafe { CStr::from_ptr(filename) }.to_str() {
+    Ok(s) => s,
+    Err(_) => return 0,
+  };
+  for cb in output_snapshot().iter().rev() {
+    if (cb.match_url)(url) {
+      return 1;
+    }
+  }
+  0
+}
+
+unsafe extern "C" fn trampoline_output_open(filename: *const c_char) -> *mut c_void {
+  if filename.is_null() {
+    return std::ptr::null_mut();
+  }
+  // SAFETY: see `trampoline_match`.
+  let url = match unsafe { CStr::from_ptr(filename) }.to_str() {
+    Ok(s) => s,
+    Err(_) => return std::ptr::null_mut(),
+  };
+  // Newest-first — the most recent matching registration receives the output.
+  match output_snapshot().into_iter().rev().find(|cb| (cb.match_url)(url)) {
+    Some(callback) => Box::into_raw(Box::new(OutputState {
+      callback,
+      url: url.to_owned(),
+      bytes: Vec::new(),
+    })) as *mut c_void,
+    None => std::ptr::null_mut(),
+  }
+}
+
+unsafe extern "C" fn trampoline_output_write(
+  context: *mut c_void,
+  buffer: *const c_char,
+  len: c_int,
+) -> c_int {
+  if context.is_null() || len < 0 {
+    return -1;
+  }
+  if len == 0 {
+    return 0;
+  }
+  if buffer.is_null() {
+    return -1;
+  }
+  // SAFETY: `context` came from `Box::into_raw` in
+  // `trampoline_output_open` and is not yet reclaimed; libxml2 passes
+  // `len` readable bytes at `buffer`.
+  let state = unsafe { &mut *(context as *mut OutputState) };
+  let bytes = unsafe { std::slice::from_raw_parts(buffer as *const u8, len as usize) };
+  state.bytes.extend_from_slice(bytes);
+  len
+}
+
+unsafe extern "C" fn trampoline_output_close(context: *mut c_void) -> c_int {
+  if context.is_null() {
+    return -1;
+  }
+  // SAFETY: unique reclamation site for the box from `trampoline_output_open`.
+  let state = unsafe { Box::from_raw(context as *mut OutputState) };
+  let OutputState {
+    callback,
+    url,
+    bytes,
+  } = *state;
+  match (callback.store)(&url, bytes) {
+    Ok(()) => 0,
+    Err(error) => {
+      LAST_OUTPUT_ERROR.with(|last| *last.borrow_mut() = Some(error));
+      -1
+    }
+  }
+}
+
+thread_local! {
+  /// The latest error returned by a `store` closure on this thread.
+  /// libxml2 only sees a failed close (and some save paths drop even
+  /// that), so callers that want the closure's own error collect it here.
+  static LAST_OUTPUT_ERROR: RefCell<Option<std::io::Error>> = const { RefCell::new(None) };
+}
+
+/// Take the error recorded by the latest failing `store` closure on this thread
+pub(crate) fn take_output_error() -> Option<std::io::Error> {
+  LAST_OUTPUT_ERROR.with(|last| last.borrow_mut().take())
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -293,5 +739,163 @@ mod tests {
       0,
       "older registration should not be consulted",
     );
+
+    // 6. Scoped registrations stop matching once their guard is dropped.
+    let guard = register_scoped_input_callback(
+      |url| url.starts_with("scoped:///"),
+      |_| Some(SAMPLE_XML.to_vec()),
+    );
+    assert!(read_file_via_libxml2("scoped:///sample.xml"));
+    drop(guard);
+    assert!(!read_file_via_libxml2("scoped:///sample.xml"));
+
+    // 7. A per-parse resolver serves that parse's external DTD only, and
+    //    falls through to the registry when it declines a URL.
+    use crate::parser::{Parser, ParserOptions};
+    let _shared = register_scoped_input_callback(
+      |url| url == "shared:///farewell.dtd",
+      |_| Some(b"<!ENTITY farewell \"bye\">".to_vec()),
+    );
+    let resolver = |url: &str| {
+      (url == "tenant:///greeting.dtd").then(|| b"<!ENTITY greeting \"hello\">".to_vec())
+    };
+    let parse = |input: &str, resolver: Option<&InputResolver>| {
+      let options = ParserOptions {
+        dtd_load: true,
+        no_ent: true,
+        resolver,
+        ..ParserOptions::default()
+      };
+      let doc = Parser::default()
+        .parse_string_with_options(input, options)
+        .unwrap();
+      doc.get_root_element().unwrap().get_content()
+    };
+    let greeting = r#"<!DOCTYPE r SYSTEM "tenant:///greeting.dtd"><r>&greeting;</r>"#;
+    let farewell = r#"<!DOCTYPE r SYSTEM "shared:///farewell.dtd"><r>&farewell;</r>"#;
+    assert_eq!(parse(greeting, Some(&resolver)), "hello");
+    assert_eq!(parse(farewell, Some(&resolver)), "bye");
+    assert_eq!(parse(greeting, None), "");
+    assert!(!read_file_via_libxml2("tenant:///greeting.dtd"));
+
+    // 8. Reader-backed callbacks are pulled chunk by chunk, and a read
+    //    error is reported to libxml2 (returning -1) and fails the load.
+    static READS: AtomicUsize = AtomicUsize::new(0);
+    let _reader = register_scoped_input_reader(
+      |url| url.starts_with("stream:///"),
+      |url| {
+        let fail_after = (url == "stream:///broken.xml").then_some(SAMPLE_XML.len() / 2);
+        Some(Box::new(ChunkedReader {
+          data: SAMPLE_XML,
+          position: 0,
+          fail_after,
+        }) as Box<dyn Read + Send>)
+      },
+    );
+    assert!(read_file_via_libxml2("stream:///sample.xml"));
+    assert!(READS.load(Ordering::SeqCst) > 1, "input should be read in chunks");
+    assert!(!read_file_via_libxml2("stream:///broken.xml"));
+
+    /// Serves `data` a few bytes at a time, failing once `fail_after` bytes are served
+    struct ChunkedReader {
+      data:       &'static [u8],
+      position:   usize,
+      fail_after: Option<usize>,
+    }
+
+    impl Read for ChunkedReader {
+      fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        READS.fetch_add(1, Ordering::SeqCst);
+        if self.fail_after.is_some_and(|limit| self.position >= limit) {
+          return Err(std::io::Error::other("connection reset"));
+        }
+        let n = buf.len().min(8).min(self.data.len() - self.position);
+        buf[..n].copy_from_slice(&self.data[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+      }
+    }
+  }
+
+  /// Output callbacks get their own `#[test]`: saving doesn't touch
+  /// the input-callback path, so it can run alongside the scenarios above.
+  #[test]
+  fn output_callback_scenarios() {
+    use crate::parser::Parser;
+    use crate::tree::{OutputOptions, SaveOptions};
+    use std::collections::HashMap;
+
+    static STORED: Mutex<Option<HashMap<String, Vec<u8>>>> = Mutex::new(None);
+    register_output_callback(
+      |url| url.starts_with("memory:///"),
+      |url, bytes| {
+        let mut stored = STORED.lock().unwrap();
+        stored.get_or_insert_with(HashMap::new).insert(url.to_owned(), bytes);
+        Ok(())
+      },
+    );
+    register_output_callback(
+      |url| url.starts_with("readonly:///"),
+      |_, _| Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "read-only store")),
+    );
+    let stored = |url: &str| STORED.lock().unwrap().as_ref().and_then(|s| s.get(url).cloned());
+
+    let doc = Parser::default().parse_string("<root><child/></root>").unwrap();
+
+    // 1. `save_file` writes through the registered callback.
+    assert!(doc.save_file("memory:///plain.xml").is_ok());
+    let plain: &[u8] = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<root><child/></root>\n";
+    assert_eq!(stored("memory:///plain.xml").unwrap(), plain);
+
+    // 2. `save_file_with_options` applies the options on the same path.
+    let options = OutputOptions {
+      save: SaveOptions {
+        format: true,
+        no_declaration: true,
+        ..SaveOptions::default()
+      },
+      indent: Some("\t"),
+      ..OutputOptions::default()
+    };
+    doc
+      .save_file_with_options("memory:///formatted.xml", &options)
+      .unwrap();
+    assert_eq!(
+      stored("memory:///formatted.xml").unwrap(),
+      b"<root>\n\t<child/>\n</root>\n"
+    );
+
+    // 3. A failing `store` fails the save, with its own error where possible.
+    assert!(doc.save_file("readonly:///x.xml").is_err());
+    let error = doc
+      .save_file_with_options("readonly:///x.xml", &OutputOptions::default())
+      .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+    assert_eq!(error.to_string(), "read-only store");
+
+    // 4. Unclaimed URLs still reach libxml2's default file writer.
+    let path = std::env::temp_dir().join(format!("libxml-output-{}.xml", std::process::id()));
+    let path_str = path.to_str().unwrap();
+    assert!(doc.save_file(path_str).is_ok());
+    assert_eq!(std::fs::read(&path).unwrap(), plain);
+    std::fs::remove_file(&path).unwrap();
+    assert!(stored(path_str).is_none());
+
+    // 5. Scoped registrations stop matching once their guard is dropped,
+    //    and receive the whole output in a single call.
+    static SCOPED_CALLS: AtomicUsize = AtomicUsize::new(0);
+    let guard = register_scoped_output_callback(
+      |url| url.starts_with("scoped-output:///"),
+      move |_, bytes| {
+        SCOPED_CALLS.fetch_add(1, Ordering::SeqCst);
+        assert_eq!(bytes, plain);
+        Ok(())
+      },
+    );
+    assert!(doc.save_file("scoped-output:///x.xml").is_ok());
+    assert_eq!(SCOPED_CALLS.load(Ordering::SeqCst), 1);
+    drop(guard);
+    assert!(doc.save_file("scoped-output:///x.xml").is_err());
+    assert_eq!(SCOPED_CALLS.load(Ordering::SeqCst), 1);
   }
 }
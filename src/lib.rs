@@ -27,6 +27,19 @@ pub mod schemas;
 /// Read-only parallel primitives
 pub mod readonly;
 
+/// CSS selectors translated to `XPath`, see `Node::select`
+pub mod css;
+
+/// Streamable XPath-subset patterns for fast node selection
+pub mod pattern;
+
+/// XML Catalogs resolving public/system identifiers and URIs to local resources
+pub mod catalog;
+
+/// XML Signature helpers: reference digests and `SignedInfo` canonicalization
+#[cfg(feature = "xmldsig")]
+pub mod xmldsig;
+
 /// Custom input callbacks for `xmlRegisterInputCallbacks` — bundle
 /// XSLT stylesheets / RNG schemas inside the binary and serve them
 /// through a user-defined URL scheme (e.g. `embed:///foo.xsl`).
@@ -2,6 +2,8 @@
 
 use crate::bindings::*;
 use crate::c_helpers::*;
+use crate::catalog::Catalog;
+use crate::io::InputResolver;
 use crate::tree::*;
 
 use std::convert::AsRef;
@@ -18,7 +20,8 @@ use std::str;
 
 enum XmlParserOption {
   Recover = 1,
-  Nodefdtd = 4,
+  Noent = 2,
+  Dtdload = 4,
   Noerror = 32,
   Nowarning = 64,
   Pedantic = 128,
@@ -28,6 +31,7 @@ enum XmlParserOption {
   Compact = 65_536,
   Huge = 524_288,
   Ignoreenc = 2_097_152,
+  Biglines = 4_194_304,
 }
 
 enum HtmlParserOption {
@@ -48,7 +52,7 @@ enum HtmlParserOption {
 pub struct ParserOptions<'a> {
   /// Relaxed parsing
   pub recover: bool,
-  /// do not default a doctype if not found
+  /// do not default a doctype if not found (HTML only)
   pub no_def_dtd: bool,
   /// do not default a doctype if not found
   pub no_error: bool,
@@ -70,6 +74,24 @@ pub struct ParserOptions<'a> {
   pub ignore_enc: bool,
   /// manually-specified encoding
   pub encoding: Option<&'a str>,
+  /// load the external DTD subset (XML only)
+  pub dtd_load: bool,
+  /// substitute entities (XML only)
+  pub no_ent: bool,
+  /// store line numbers above 65535 (XML only), see `Node::get_line_number`
+  pub big_lines: bool,
+  /// resolver for the external resources (DTDs, entities) of this parse only.
+  /// Consulted before the `io::register_input_callback` registry; `None`
+  /// from the resolver falls through to it, and then to libxml2's default
+  /// file and HTTP loaders unless `no_net` is set. The external DTD subset
+  /// is only loaded with `dtd_load`.
+  pub resolver: Option<&'a InputResolver<'a>>,
+  /// catalog resolving the public and system identifiers of this parse's
+  /// external DTDs and entities, before libxml2 loads them. Identifiers
+  /// without a catalog entry are loaded as given by libxml2's default file
+  /// and HTTP loaders unless `no_net` is set. The external DTD subset is
+  /// only loaded with `dtd_load`.
+  pub catalog: Option<&'a Catalog>,
 }
 
 impl ParserOptions<'_> {
@@ -87,10 +109,28 @@ impl ParserOptions<'_> {
           0
         }
       };
+      (
+        html $condition:expr => $variant:ident
+      ) => {
+        if $condition && *format == ParseFormat::HTML {
+          HtmlParserOption::$variant as i32
+        } else {
+          0
+        }
+      };
+      (
+        xml $condition:expr => $variant:ident
+      ) => {
+        if $condition && *format == ParseFormat::XML {
+          XmlParserOption::$variant as i32
+        } else {
+          0
+        }
+      };
     }
     // return the combined flags
     to_option_flag!(self.recover => Recover)
-      + to_option_flag!(self.no_def_dtd => Nodefdtd)
+      + to_option_flag!(html self.no_def_dtd => Nodefdtd)
       + to_option_flag!(self.no_error => Noerror)
       + to_option_flag!(self.no_warning => Nowarning)
       + to_option_flag!(self.pedantic => Pedantic)
@@ -100,6 +140,9 @@ impl ParserOptions<'_> {
       + to_option_flag!(self.huge => Huge)
       + to_option_flag!(self.compact => Compact)
       + to_option_flag!(self.ignore_enc => Ignoreenc)
+      + to_option_flag!(xml self.dtd_load => Dtdload)
+      + to_option_flag!(xml self.no_ent => Noent)
+      + to_option_flag!(xml self.big_lines => Biglines)
   }
 }
 
@@ -118,6 +161,11 @@ impl Default for ParserOptions<'_> {
       compact: false,
       ignore_enc: false,
       encoding: None,
+      dtd_load: false,
+      no_ent: false,
+      big_lines: false,
+      resolver: None,
+      catalog: None,
     }
   }
 }
@@ -271,6 +319,8 @@ impl Parser {
     }
 
     let options = parser_options.to_flags(&self.format);
+    let _resolver = crate::io::ParseResolverScope::new(parser_options.resolver);
+    let _catalog = crate::catalog::CatalogScope::new(parser_options.catalog);
 
     match self.format {
       ParseFormat::XML => unsafe {
@@ -321,6 +371,8 @@ impl Parser {
     let url_ptr = DEFAULT_URL;
 
     let options = parser_options.to_flags(&self.format);
+    let _resolver = crate::io::ParseResolverScope::new(parser_options.resolver);
+    let _catalog = crate::catalog::CatalogScope::new(parser_options.catalog);
 
     match self.format {
       ParseFormat::XML => unsafe {
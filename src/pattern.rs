@@ -0,0 +1,204 @@
+//! Streamable XPath-subset patterns (`xmlPattern`)
+//!
+//! A `Pattern` is compiled from the subset of XPath that libxml2's
+//! `xmlPatterncompile` accepts: location paths made of `/`, `//`, `.`,
+//! element and attribute name tests (optionally prefixed) and `*`, combined
+//! with `|`. No predicates or functions. In exchange, matching is a cheap
+//! check against a single node, and streamable patterns can follow a
+//! document as it is read (e.g. from an `xmlTextReader` loop) without
+//! building or querying a tree.
+//!
+//! ```
+//! use libxml::parser::Parser;
+//! use libxml::pattern::Pattern;
+//!
+//! let doc = Parser::default().parse_string("<feed><entry/></feed>").unwrap();
+//! let entry = doc.get_root_readonly().unwrap().get_first_element_child().unwrap();
+//!
+//! let pattern = Pattern::new("/feed/entry", &[]).unwrap();
+//! assert!(pattern.matches_readonly(entry));
+//!
+//! let mut stream = pattern.stream().unwrap();
+//! stream.push_document().unwrap();
+//! assert_eq!(stream.push("feed", None), Ok(false));
+//! assert_eq!(stream.push("entry", None), Ok(true));
+//! ```
+
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::ptr;
+
+use crate::bindings::*;
+use crate::readonly::RoNode;
+use crate::tree::Node;
+
+/// A compiled `xmlPattern`
+pub struct Pattern {
+  ptr: xmlPatternPtr,
+}
+
+impl Drop for Pattern {
+  ///free the compiled pattern when it goes out of scope
+  fn drop(&mut self) {
+    unsafe {
+      xmlFreePattern(self.ptr);
+    }
+  }
+}
+
+impl Pattern {
+  /// Compile `pattern`, resolving its prefixes with the `(prefix, href)` pairs in `namespaces`.
+  /// Returns `Err(())` if libxml2 can not compile the pattern.
+  pub fn new(pattern: &str, namespaces: &[(&str, &str)]) -> Result<Self, ()> {
+    let c_pattern = CString::new(pattern).map_err(|_| ())?;
+    let mut c_namespaces = Vec::with_capacity(namespaces.len() * 2);
+    for (prefix, href) in namespaces {
+      c_namespaces.push(CString::new(*href).map_err(|_| ())?);
+      c_namespaces.push(CString::new(*prefix).map_err(|_| ())?);
+    }
+    // libxml2 expects a NULL-terminated array of [href, prefix] pairs
+    let mut ns_ptrs: Vec<*const xmlChar> = c_namespaces
+      .iter()
+      .map(|s| s.as_bytes().as_ptr())
+      .chain([ptr::null(), ptr::null()])
+      .collect();
+    let ns_arg = if namespaces.is_empty() {
+      ptr::null_mut()
+    } else {
+      ns_ptrs.as_mut_ptr()
+    };
+    let ptr = unsafe {
+      xmlPatterncompile(
+        c_pattern.as_bytes().as_ptr(),
+        ptr::null_mut(),
+        xmlPatternFlags_XML_PATTERN_DEFAULT as i32,
+        ns_arg,
+      )
+    };
+    if ptr.is_null() {
+      Err(())
+    } else {
+      Ok(Pattern { ptr })
+    }
+  }
+
+  /// Returns the raw libxml2 pattern pointer behind the struct
+  pub fn as_ptr(&self) -> xmlPatternPtr {
+    self.ptr
+  }
+
+  /// Checks whether `node` is selected by the pattern
+  pub fn matches(&self, node: &Node) -> bool {
+    unsafe { xmlPatternMatch(self.ptr, node.node_ptr()) == 1 }
+  }
+
+  /// Checks whether the read-only `node` is selected by the pattern
+  pub fn matches_readonly(&self, node: RoNode) -> bool {
+    unsafe { xmlPatternMatch(self.ptr, node.node_ptr()) == 1 }
+  }
+
+  /// Whether the pattern can be evaluated in streaming mode, see `Pattern::stream`
+  pub fn is_streamable(&self) -> bool {
+    unsafe { xmlPatternStreamable(self.ptr) == 1 }
+  }
+
+  /// Maximum depth at which the pattern can match, `None` if unbounded (`//`)
+  pub fn max_depth(&self) -> Option<usize> {
+    match unsafe { xmlPatternMaxDepth(self.ptr) } {
+      depth if depth >= 0 => Some(depth as usize),
+      _ => None,
+    }
+  }
+
+  /// Start a streaming evaluation of the pattern.
+  /// Returns `Err(())` if the pattern is not streamable.
+  pub fn stream(&self) -> Result<PatternStream<'_>, ()> {
+    let ptr = unsafe { xmlPatternGetStreamCtxt(self.ptr) };
+    if ptr.is_null() {
+      Err(())
+    } else {
+      Ok(PatternStream {
+        ptr,
+        pattern: PhantomData,
+      })
+    }
+  }
+}
+
+/// Streaming evaluation state of a `Pattern`, fed one event at a time.
+///
+/// Call `push_document` when a document starts, `push` when an element starts
+/// and `pop` when it ends; the return value of `push` tells whether the element
+/// just entered is selected by the pattern.
+pub struct PatternStream<'a> {
+  ptr: xmlStreamCtxtPtr,
+  pattern: PhantomData<&'a Pattern>,
+}
+
+impl Drop for PatternStream<'_> {
+  ///free the stream context when it goes out of scope
+  fn drop(&mut self) {
+    unsafe {
+      xmlFreeStreamCtxt(self.ptr);
+    }
+  }
+}
+
+impl PatternStream<'_> {
+  /// Signal the start of a new document. Required before the root element
+  /// for patterns anchored at the root (`/...`), and resets any previous state.
+  pub fn push_document(&mut self) -> Result<(), ()> {
+    let status = unsafe { xmlStreamPush(self.ptr, ptr::null(), ptr::null()) };
+    stream_status(status).map(|_| ())
+  }
+
+  /// Enter an element with local `name` in namespace `ns`; `Ok(true)` if it matches
+  pub fn push(&mut self, name: &str, ns: Option<&str>) -> Result<bool, ()> {
+    let c_name = CString::new(name).map_err(|_| ())?;
+    let c_ns = ns.map(CString::new).transpose().map_err(|_| ())?;
+    let status = unsafe {
+      xmlStreamPush(
+        self.ptr,
+        c_name.as_bytes().as_ptr(),
+        c_ns.as_ref().map_or(ptr::null(), |ns| ns.as_bytes().as_ptr()),
+      )
+    };
+    stream_status(status)
+  }
+
+  /// Signal an attribute `name` in namespace `ns` on the current element; `Ok(true)` if it matches
+  pub fn push_attribute(&mut self, name: &str, ns: Option<&str>) -> Result<bool, ()> {
+    let c_name = CString::new(name).map_err(|_| ())?;
+    let c_ns = ns.map(CString::new).transpose().map_err(|_| ())?;
+    let status = unsafe {
+      xmlStreamPushAttr(
+        self.ptr,
+        c_name.as_bytes().as_ptr(),
+        c_ns.as_ref().map_or(ptr::null(), |ns| ns.as_bytes().as_ptr()),
+      )
+    };
+    stream_status(status)
+  }
+
+  /// Leave the current element
+  pub fn pop(&mut self) -> Result<(), ()> {
+    if unsafe { xmlStreamPop(self.ptr) } < 0 {
+      Err(())
+    } else {
+      Ok(())
+    }
+  }
+
+  /// Whether the stream also needs to see non-element nodes (text, comments, ...)
+  pub fn wants_any_node(&self) -> bool {
+    unsafe { xmlStreamWantsAnyNode(self.ptr) == 1 }
+  }
+}
+
+fn stream_status(status: i32) -> Result<bool, ()> {
+  match status {
+    0 => Ok(false),
+    1 => Ok(true),
+    _ => Err(()),
+  }
+}
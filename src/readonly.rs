@@ -1,3 +1,5 @@
+mod document;
 mod tree;
 
+pub use self::document::{FrozenContext, FrozenDocument, FrozenObject};
 pub use self::tree::RoNode;
@@ -0,0 +1,198 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::bindings::*;
+use crate::readonly::RoNode;
+use crate::tree::Document;
+use crate::xpath::{Context, NamespaceRegistration, Object, ObjectType};
+
+/// A document that can no longer be mutated, shareable across threads.
+///
+/// Obtained via `Document::freeze`. libxml2 allows any number of threads to
+/// read the same tree, and XPath evaluation is such a read as long as every
+/// thread uses its own context — see `FrozenContext`.
+#[derive(Debug)]
+pub struct FrozenDocument {
+  doc_ptr: xmlDocPtr,
+}
+
+// we claim Sync and Send, as no mutable access to the tree is possible while frozen
+unsafe impl Sync for FrozenDocument {}
+unsafe impl Send for FrozenDocument {}
+
+impl Drop for FrozenDocument {
+  ///Free document when it goes out of scope
+  fn drop(&mut self) {
+    unsafe {
+      if !self.doc_ptr.is_null() {
+        xmlFreeDoc(self.doc_ptr);
+      }
+    }
+  }
+}
+
+impl FrozenDocument {
+  pub(crate) fn new_ptr(doc_ptr: xmlDocPtr) -> Self {
+    FrozenDocument { doc_ptr }
+  }
+
+  /// Obtain the underlying libxml2 `xmlDocPtr` for this document
+  pub fn doc_ptr(&self) -> xmlDocPtr {
+    self.doc_ptr
+  }
+
+  /// Get the root element of the document (read-only)
+  pub fn get_root_readonly(&self) -> Option<RoNode> {
+    let node_ptr = unsafe { xmlDocGetRootElement(self.doc_ptr) };
+    if node_ptr.is_null() {
+      None
+    } else {
+      Some(RoNode(node_ptr))
+    }
+  }
+
+  /// Create an XPath context for the calling thread
+  pub fn xpath_context(&self) -> Result<FrozenContext<'_>, ()> {
+    FrozenContext::new(self)
+  }
+
+  /// Turn the frozen document back into a mutable `Document`
+  pub fn thaw(mut self) -> Document {
+    let doc_ptr = std::mem::replace(&mut self.doc_ptr, std::ptr::null_mut());
+    Document::new_ptr(doc_ptr)
+  }
+}
+
+/// An XPath context over a `FrozenDocument`.
+///
+/// Contexts are cheap and deliberately neither `Send` nor `Sync`: create one
+/// per thread (e.g. with rayon's `map_init`) and evaluate concurrently.
+/// Results are returned as `RoNode`s, values or `FrozenObject`s, never as `Node`s.
+pub struct FrozenContext<'a> {
+  context: Context,
+  document: PhantomData<&'a FrozenDocument>,
+}
+
+impl<'a> FrozenContext<'a> {
+  /// create the xpath context for a frozen document
+  pub fn new(doc: &'a FrozenDocument) -> Result<Self, ()> {
+    Ok(FrozenContext {
+      context: Context::new_untracked(doc.doc_ptr())?,
+      document: PhantomData,
+    })
+  }
+
+  /// Returns the raw libxml2 context pointer behind the struct
+  pub fn as_ptr(&self) -> xmlXPathContextPtr {
+    self.context.as_ptr()
+  }
+
+  /// Register a namespace prefix-href pair on the xpath context
+  pub fn register_namespace(&self, prefix: &str, href: &str) -> Result<(), ()> {
+    self.context.register_namespace(prefix, href)
+  }
+
+  /// Look up the href registered for `prefix` on the xpath context
+  pub fn lookup_namespace(&self, prefix: &str) -> Option<String> {
+    self.context.lookup_namespace(prefix)
+  }
+
+  /// Register all namespaces in scope at `node`
+  pub fn register_namespaces_in_scope(
+    &self,
+    node: RoNode,
+    options: &NamespaceRegistration,
+  ) -> Result<(), ()> {
+    self.context.register_namespaces_in_scope_readonly(node, options)
+  }
+
+  /// Register the namespaces declared anywhere in the document
+  pub fn register_document_namespaces(&self, options: &NamespaceRegistration) -> Result<(), ()> {
+    self.context.register_document_namespaces(options)
+  }
+
+  /// localize xpath context to a specific node
+  pub fn set_context_node(&mut self, node: RoNode) -> Result<(), ()> {
+    self.context.set_context_node_readonly(node)
+  }
+
+  /// evaluate an xpath, at a specified node or the context node
+  pub fn evaluate(&self, xpath: &str, node_opt: Option<RoNode>) -> Result<FrozenObject<'a>, ()> {
+    let object = match node_opt {
+      Some(node) => self.context.node_evaluate_readonly(xpath, node),
+      None => self.context.evaluate(xpath),
+    }?;
+    Ok(FrozenObject {
+      object,
+      document: PhantomData,
+    })
+  }
+
+  /// find nodes via xpath, at a specified node or the context node
+  pub fn findnodes(&mut self, xpath: &str, node_opt: Option<RoNode>) -> Result<Vec<RoNode>, ()> {
+    self.context.findnodes_readonly(xpath, node_opt)
+  }
+
+  /// find literal values via xpath, at a specified node or the context node
+  pub fn findvalues(&mut self, xpath: &str, node_opt: Option<RoNode>) -> Result<Vec<String>, ()> {
+    self.context.findvalues_readonly(xpath, node_opt)
+  }
+
+  /// find a literal value via xpath, at a specified node or the context node
+  pub fn findvalue(&mut self, xpath: &str, node_opt: Option<RoNode>) -> Result<String, ()> {
+    self.context.findvalue_readonly(xpath, node_opt)
+  }
+}
+
+/// The result of evaluating an xpath on a `FrozenContext`.
+///
+/// Borrows the `FrozenDocument`, and only hands out its nodes as `RoNode`s.
+#[derive(Debug)]
+pub struct FrozenObject<'a> {
+  object: Object,
+  document: PhantomData<&'a FrozenDocument>,
+}
+
+impl FrozenObject<'_> {
+  /// Returns the raw libxml2 object pointer behind the struct
+  pub fn as_ptr(&self) -> xmlXPathObjectPtr {
+    self.object.ptr
+  }
+
+  /// The type of the evaluation result
+  pub fn get_type(&self) -> ObjectType {
+    self.object.get_type()
+  }
+
+  /// The result converted to a boolean, following the XPath `boolean()` function
+  pub fn to_boolean(&self) -> bool {
+    self.object.to_boolean()
+  }
+
+  /// The result converted to a number, following the XPath `number()` function
+  pub fn to_number(&self) -> f64 {
+    self.object.to_number()
+  }
+
+  /// get the number of nodes in the result set
+  pub fn get_number_of_nodes(&self) -> usize {
+    self.object.get_number_of_nodes()
+  }
+
+  /// returns the result set as a vector of `RoNode` objects
+  pub fn get_readonly_nodes_as_vec(&self) -> Vec<RoNode> {
+    self.object.get_readonly_nodes_as_vec()
+  }
+
+  /// returns the result set as a vector of Strings
+  pub fn get_nodes_as_str(&self) -> Vec<String> {
+    self.object.get_nodes_as_str()
+  }
+}
+
+impl fmt::Display for FrozenObject<'_> {
+  /// use if the XPath used was meant to return a string, such as string(//foo/@attr)
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.object.fmt(f)
+  }
+}
@@ -1,4 +1,5 @@
 use libc::{c_char, c_void};
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::ffi::{CStr, CString};
 use std::ptr;
@@ -6,10 +7,12 @@ use std::str;
 
 use crate::bindings::*;
 use crate::c_helpers::*;
+use crate::css::SelectorError;
 use crate::tree::namespace::Namespace;
 use crate::tree::nodetype::NodeType;
+use crate::tree::traversal::{Ancestors, Children, Descendants, ElementChildren, Siblings, Traverse};
 use crate::tree::Document;
-use crate::xpath::Context;
+use crate::xpath::{Context, Object};
 
 /// Lightweight struct for read-only parallel processing
 #[derive(Debug, Copy, Clone)]
@@ -166,6 +169,55 @@ impl RoNode {
       .collect::<Vec<RoNode>>()
   }
 
+  /// Iterates over the child nodes, without allocating
+  pub fn children(self) -> Children {
+    Children {
+      next: self.get_first_child(),
+    }
+  }
+
+  /// Iterates over the child elements, without allocating
+  pub fn element_children(self) -> ElementChildren {
+    ElementChildren {
+      children: self.children(),
+    }
+  }
+
+  /// Iterates over the descendants in document order, with their depth
+  /// below this node (1 for children)
+  pub fn descendants(self) -> Descendants {
+    Descendants::new(self)
+  }
+
+  /// Iterates over the following siblings, nearest first
+  pub fn following_siblings(self) -> Siblings {
+    Siblings {
+      next: self.get_next_sibling(),
+      following: true,
+    }
+  }
+
+  /// Iterates over the preceding siblings, nearest first
+  pub fn preceding_siblings(self) -> Siblings {
+    Siblings {
+      next: self.get_prev_sibling(),
+      following: false,
+    }
+  }
+
+  /// Iterates over the ancestors, from the parent up to the document node
+  pub fn ancestors_iter(self) -> Ancestors {
+    Ancestors {
+      next: self.get_parent(),
+    }
+  }
+
+  /// Depth-first traversal of the subtree of this node, entering and
+  /// leaving every node, this one included
+  pub fn traverse(self) -> Traverse {
+    Traverse::new(self)
+  }
+
   /// Returns the parent if it exists
   pub fn get_parent(self) -> Option<RoNode> {
     let ptr = xmlGetParent(self.0);
@@ -216,6 +268,27 @@ impl RoNode {
     rust_utf8
   }
 
+  /// Returns the line of the source document the node was parsed from,
+  /// or `None` for nodes created programmatically. Lines above 65535 are
+  /// only known for documents parsed with `ParserOptions::big_lines`; the
+  /// others report 65535.
+  pub fn get_line_number(self) -> Option<usize> {
+    let line = unsafe { xmlGetLineNo(self.0) };
+    usize::try_from(line).ok().filter(|line| *line > 0)
+  }
+
+  /// Returns an XPath-like path to the node, e.g. `/root/item[2]/@id`
+  pub fn get_path(self) -> Option<String> {
+    let path_ptr = unsafe { xmlGetNodePath(self.0) };
+    if path_ptr.is_null() {
+      return None;
+    }
+    let c_string = unsafe { CStr::from_ptr(path_ptr as *const c_char) };
+    let path = c_string.to_string_lossy().into_owned();
+    bindgenFree(path_ptr as *mut c_void);
+    Some(path)
+  }
+
   /// Returns the value of property `name`
   pub fn get_property(self, name: &str) -> Option<String> {
     let c_name = CString::new(name).unwrap();
@@ -415,7 +488,12 @@ impl RoNode {
 
   /// Gets a list of namespaces associated with this node
   pub fn get_namespaces(self, doc: &Document) -> Vec<Namespace> {
-    let list_ptr_raw = unsafe { xmlGetNsList(doc.doc_ptr(), self.0) };
+    self.get_namespaces_in(doc.doc_ptr())
+  }
+
+  /// `get_namespaces` for callers that only hold the owning `xmlDocPtr`
+  pub(crate) fn get_namespaces_in(self, doc_ptr: xmlDocPtr) -> Vec<Namespace> {
+    let list_ptr_raw = unsafe { xmlGetNsList(doc_ptr, self.0) };
     if list_ptr_raw.is_null() {
       Vec::new()
     } else {
@@ -518,6 +596,58 @@ impl RoNode {
     Ok(evaluated.get_readonly_nodes_as_vec())
   }
 
+  /// Find the descendant elements matching the CSS selector `css`, see `Node::select`
+  pub fn select(self, css: &str, owner: &Document) -> Result<Vec<RoNode>, SelectorError> {
+    let xpath = crate::css::to_xpath_at(css, self.0)?;
+    self
+      .findnodes(&xpath, owner)
+      .map_err(|_| crate::css::evaluation_error(css))
+  }
+
+  /// Find the first descendant element matching the CSS selector `css`, see `Node::select`
+  pub fn select_first(
+    self,
+    css: &str,
+    owner: &Document,
+  ) -> Result<Option<RoNode>, SelectorError> {
+    Ok(self.select(css, owner)?.into_iter().next())
+  }
+
+  /// find String values via xpath, at the specified node and a given document
+  pub fn findvalues(self, xpath: &str, owner: &Document) -> Result<Vec<String>, ()> {
+    let mut context = Context::new(owner)?;
+    context.findvalues_readonly(xpath, Some(self))
+  }
+
+  /// find a literal value via xpath, at the specified node and a given document
+  pub fn findvalue(self, xpath: &str, owner: &Document) -> Result<String, ()> {
+    let mut context = Context::new(owner)?;
+    context.findvalue_readonly(xpath, Some(self))
+  }
+
+  /// Search this node for XPath `path`, and return only the first match.
+  pub fn at_xpath(
+    self,
+    path: &str,
+    ns_bindings: &[(&str, &str)],
+    owner: &Document,
+  ) -> Result<Option<RoNode>, ()> {
+    let mut context = Context::new(owner)?;
+    for (prefix, href) in ns_bindings {
+      context.register_namespace(prefix, href)?;
+    }
+    let nodes = context.findnodes_readonly(path, Some(self))?;
+
+    Ok(nodes.first().copied())
+  }
+
+  /// evaluate an xpath at the specified node, keeping the typed result
+  /// (see `Object::get_type`, `Object::to_number`, `Object::to_boolean`)
+  pub fn evaluate(self, xpath: &str, owner: &Document) -> Result<Object, ()> {
+    let context = Context::new(owner)?;
+    context.node_evaluate_readonly(xpath, self)
+  }
+
   /// Read-only nodes are always linked
   pub fn is_unlinked(self) -> bool {
     false
@@ -541,6 +671,47 @@ impl RoNode {
     }
   }
 
+  /// Compare the positions of two nodes in their document (`xmlXPathCmpNodes`).
+  /// Returns `None` for nodes that are not part of the same tree, and for
+  /// the namespace nodes of `namespace::` XPath results, which are not
+  /// linked into the tree.
+  pub fn cmp_document_order(self, other: RoNode) -> Option<Ordering> {
+    if self.is_namespace_node() || other.is_namespace_node() {
+      return None;
+    }
+    match unsafe { xmlXPathCmpNodes(self.0, other.0) } {
+      1 => Some(Ordering::Less),
+      0 => Some(Ordering::Equal),
+      -1 => Some(Ordering::Greater),
+      _ => None,
+    }
+  }
+
+  /// A total order for sorting: document order within a tree, and trees
+  /// (e.g. unlinked subtrees or other documents) ordered by address
+  pub fn document_order(self, other: RoNode) -> Ordering {
+    self.cmp_document_order(other).unwrap_or_else(|| {
+      // a namespace node (an `xmlNs`) has no parent link to follow
+      let top = |node: RoNode| match node.is_namespace_node() {
+        true => node.0 as usize,
+        false => node.ancestors_iter().last().unwrap_or(node).0 as usize,
+      };
+      top(self).cmp(&top(other)).then((self.0 as usize).cmp(&(other.0 as usize)))
+    })
+  }
+
+  /// Sort `nodes` in document order and remove duplicates, e.g. to merge
+  /// the results of several XPath queries
+  pub fn sort_in_document_order(nodes: &mut Vec<RoNode>) {
+    nodes.sort_by(|a, b| a.document_order(*b));
+    nodes.dedup();
+  }
+
+  /// Whether this is a namespace node, which is an `xmlNs` rather than an `xmlNode`
+  fn is_namespace_node(self) -> bool {
+    self.get_type() == Some(NodeType::NamespaceDecl)
+  }
+
   /// `libc::c_void` isn't hashable and cannot be made hashable
   pub fn to_hashable(self) -> usize {
     self.0 as usize
@@ -2,6 +2,8 @@
 //!
 use std::ffi::c_int;
 
+use crate::readonly::RoNode;
+
 use crate::bindings::{
   xmlC14NMode_XML_C14N_1_0, xmlC14NMode_XML_C14N_1_1, xmlC14NMode_XML_C14N_EXCLUSIVE_1_0,
 };
@@ -42,3 +44,21 @@ impl From<CanonicalizationMode> for c_int {
     c_int::from(c14n_mode as i32)
   }
 }
+
+/// Selects the part of a document to canonicalize, see `Document::canonicalize_filtered`
+pub enum CanonicalizationFilter<'a> {
+  /// Keep the nodes for which the closure returns `true`. It receives each
+  /// element, attribute, text, comment and processing instruction with its
+  /// parent; namespace declarations are kept when their element is.
+  Visible(&'a dyn Fn(RoNode, Option<RoNode>) -> bool),
+  /// Keep the node set selected by an XPath expression, e.g.
+  /// `(//. | //@* | //namespace::*)[not(ancestor-or-self::ds:Signature)]`.
+  /// As for XML-DSig XPath transforms, only the selected nodes are output:
+  /// select attributes and namespace nodes explicitly to keep them.
+  XPath {
+    /// the XPath expression, evaluated at the document
+    expression: &'a str,
+    /// `(prefix, href)` bindings for the prefixes of `expression`
+    namespaces: &'a [(&'a str, &'a str)],
+  },
+}
@@ -1,6 +1,6 @@
 //! Document feature set
 //!
-use libc::{c_char, c_int};
+use libc::{c_char, c_int, c_void};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
@@ -10,8 +10,10 @@ use std::rc::{Rc, Weak};
 use std::str;
 
 use crate::bindings::*;
-use crate::readonly::RoNode;
+use crate::c_helpers::bindgenFree;
+use crate::readonly::{FrozenDocument, RoNode};
 use crate::tree::node::Node;
+use crate::xpath::{Context, Object, XPointerError};
 
 pub(crate) type DocumentRef = Rc<RefCell<_Document>>;
 pub(crate) type DocumentWeak = Weak<RefCell<_Document>>;
@@ -37,6 +39,51 @@ pub struct SaveOptions {
   pub non_significant_whitespace: bool,
 }
 
+impl SaveOptions {
+  /// The libxml2 `xmlSaveOption` flags selected by these options
+  pub(crate) fn to_flags(self) -> i32 {
+    let mut xml_options = 0;
+
+    if self.format {
+      xml_options += xmlSaveOption_XML_SAVE_FORMAT;
+    }
+    if self.no_declaration {
+      xml_options += xmlSaveOption_XML_SAVE_NO_DECL;
+    }
+    if self.no_empty_tags {
+      xml_options += xmlSaveOption_XML_SAVE_NO_EMPTY;
+    }
+    if self.no_xhtml {
+      xml_options += xmlSaveOption_XML_SAVE_NO_XHTML;
+    }
+    if self.xhtml {
+      xml_options += xmlSaveOption_XML_SAVE_XHTML;
+    }
+    if self.as_xml {
+      xml_options += xmlSaveOption_XML_SAVE_AS_XML;
+    }
+    if self.as_html {
+      xml_options += xmlSaveOption_XML_SAVE_AS_HTML;
+    }
+    if self.non_significant_whitespace {
+      xml_options += xmlSaveOption_XML_SAVE_WSNONSIG;
+    }
+
+    xml_options as i32
+  }
+}
+
+/// The name and identifiers of a `<!DOCTYPE ...>` declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Doctype {
+  /// name of the root element
+  pub name: String,
+  /// public identifier of the external subset
+  pub public_id: Option<String>,
+  /// system identifier (URI) of the external subset
+  pub system_id: Option<String>,
+}
+
 #[derive(Debug)]
 pub(crate) struct _Document {
   /// pointer to a libxml document
@@ -58,6 +105,11 @@ impl _Document {
   pub(crate) fn forget_node(&mut self, node_ptr: xmlNodePtr) {
     self.nodes.remove(&node_ptr);
   }
+  /// Internal bookkeeping function: true if a `Node` wrapper is held
+  /// anywhere besides the bookkeeping table itself
+  pub(crate) fn has_live_nodes(&self) -> bool {
+    self.nodes.values().any(|node| node.wrapper_count() > 1)
+  }
 }
 
 /// A libxml2 Document
@@ -65,8 +117,11 @@ impl _Document {
 pub struct Document(pub(crate) DocumentRef);
 
 impl Drop for _Document {
-  ///Free document when it goes out of scope
+  ///Free document when it goes out of scope, with the nodes created for it but never attached
   fn drop(&mut self) {
+    for node in self.nodes.values() {
+      node.free_if_unattached();
+    }
     unsafe {
       if !self.doc_ptr.is_null() {
         xmlFreeDoc(self.doc_ptr);
@@ -121,11 +176,13 @@ impl Document {
     }))
   }
 
-  /// Write document to `filename`
+  /// Write document to `filename` in UTF-8, as `save_file_with_options`
+  /// does by default.
+  /// `filename` may be any URL claimed by `io::register_output_callback`.
   pub fn save_file(&self, filename: &str) -> Result<c_int, ()> {
     let c_filename = CString::new(filename).unwrap();
     unsafe {
-      let retval = xmlSaveFile(c_filename.as_ptr(), self.doc_ptr());
+      let retval = xmlSaveFileEnc(c_filename.as_ptr(), self.doc_ptr(), c"UTF-8".as_ptr());
       if retval < 0 {
         return Err(());
       }
@@ -182,12 +239,182 @@ impl Document {
     unsafe {
       let dtd = xmlGetIntSubset(self.doc_ptr());
       if !dtd.is_null() {
+        self.0.borrow_mut().forget_node(dtd as xmlNodePtr);
         xmlUnlinkNode(dtd as xmlNodePtr);
         xmlFreeDtd(dtd);
       }
     }
   }
 
+  /// Create the internal DTD subset, serialized as
+  /// `<!DOCTYPE name PUBLIC "public_id" "system_id">` before the root element.
+  ///
+  /// Returns `Err(())` if the document already has an internal subset (see
+  /// `remove_internal_subset`), if a value contains a NUL byte, or if
+  /// libxml2 returns NULL.
+  pub fn create_internal_subset(
+    &mut self,
+    name: &str,
+    public_id: Option<&str>,
+    system_id: Option<&str>,
+  ) -> Result<(), ()> {
+    let c_name = CString::new(name).map_err(|_| ())?;
+    let c_public_id = public_id.map(CString::new).transpose().map_err(|_| ())?;
+    let c_system_id = system_id.map(CString::new).transpose().map_err(|_| ())?;
+    let as_ptr = |value: &Option<CString>| match value {
+      Some(value) => value.as_bytes().as_ptr(),
+      None => ptr::null(),
+    };
+    let dtd = unsafe {
+      xmlCreateIntSubset(
+        self.doc_ptr(),
+        c_name.as_bytes().as_ptr(),
+        as_ptr(&c_public_id),
+        as_ptr(&c_system_id),
+      )
+    };
+    if dtd.is_null() {
+      Err(())
+    } else {
+      Ok(())
+    }
+  }
+
+  /// The `<!DOCTYPE ...>` declaration of the document, if any
+  pub fn get_internal_subset(&self) -> Option<Doctype> {
+    if self.doc_ptr().is_null() {
+      return None;
+    }
+    let dtd = unsafe { xmlGetIntSubset(self.doc_ptr()) };
+    if dtd.is_null() {
+      return None;
+    }
+    unsafe {
+      Some(Doctype {
+        name: to_string_option((*dtd).name).unwrap_or_default(),
+        public_id: to_string_option((*dtd).ExternalID),
+        system_id: to_string_option((*dtd).SystemID),
+      })
+    }
+  }
+
+  /// Get the element with the given ID: an `xml:id` attribute, an attribute
+  /// declared as ID by the DTD or, in HTML documents, an `id` attribute.
+  /// Elements unlinked from the tree are not found.
+  pub fn get_element_by_id(&self, id: &str) -> Option<Node> {
+    self
+      .element_by_id(id)
+      .map(|node_ptr| self.register_node(node_ptr))
+  }
+
+  /// Get the element with the given ID (read-only), see `get_element_by_id`
+  pub fn get_element_by_id_readonly(&self, id: &str) -> Option<RoNode> {
+    self.element_by_id(id).map(RoNode)
+  }
+
+  fn element_by_id(&self, id: &str) -> Option<xmlNodePtr> {
+    let doc_ptr = self.doc_ptr();
+    let c_id = CString::new(id).ok()?;
+    if doc_ptr.is_null() {
+      return None;
+    }
+    let attr = unsafe { xmlGetID(doc_ptr, c_id.as_bytes().as_ptr()) };
+    if attr.is_null() {
+      return None;
+    }
+    let element = unsafe { (*attr).parent };
+    (!element.is_null() && is_in_document(element, doc_ptr)).then_some(element)
+  }
+
+  /// The version of the XML declaration, `1.0` for new documents
+  pub fn get_version(&self) -> Option<String> {
+    self.read_declaration(|doc| doc.version)
+  }
+
+  /// Sets the version written in the XML declaration
+  pub fn set_version(&mut self, version: &str) -> Result<(), ()> {
+    self.write_declaration(|doc| &mut doc.version, Some(version))
+  }
+
+  /// The encoding declared by the parsed document, or set with
+  /// `set_encoding`; `None` for new documents.
+  pub fn get_encoding(&self) -> Option<String> {
+    self.read_declaration(|doc| doc.encoding)
+  }
+
+  /// Sets the declared encoding of the document. All serializers produce,
+  /// and declare, UTF-8 by default. Only `to_bytes_with_options` and
+  /// `save_file_with_options` encode the document in its declared encoding,
+  /// when `OutputOptions::declared_encoding` is set and no
+  /// `OutputOptions::encoding` is given; `write_to`, `save_file` and the
+  /// `String` serializers always use UTF-8.
+  pub fn set_encoding(&mut self, encoding: Option<&str>) -> Result<(), ()> {
+    self.write_declaration(|doc| &mut doc.encoding, encoding)
+  }
+
+  /// The `standalone` value of the XML declaration, `None` when the
+  /// declaration has none
+  pub fn get_standalone(&self) -> Option<bool> {
+    if self.doc_ptr().is_null() {
+      return None;
+    }
+    match unsafe { (*self.doc_ptr()).standalone } {
+      1 => Some(true),
+      0 => Some(false),
+      _ => None,
+    }
+  }
+
+  /// Sets the `standalone` value of the XML declaration, or leaves it out
+  pub fn set_standalone(&mut self, standalone: Option<bool>) {
+    let doc_ptr = self.doc_ptr();
+    if !doc_ptr.is_null() {
+      unsafe {
+        (*doc_ptr).standalone = match standalone {
+          Some(true) => 1,
+          Some(false) => 0,
+          None => -1,
+        };
+      }
+    }
+  }
+
+  fn read_declaration(&self, field: impl FnOnce(&xmlDoc) -> *const xmlChar) -> Option<String> {
+    let doc_ptr = self.doc_ptr();
+    if doc_ptr.is_null() {
+      None
+    } else {
+      unsafe { to_string_option(field(&*doc_ptr)) }
+    }
+  }
+
+  /// Replace a string of the document, freeing the previous value unless
+  /// it belongs to the document's dictionary
+  fn write_declaration(
+    &mut self,
+    field: impl FnOnce(&mut xmlDoc) -> &mut *const xmlChar,
+    value: Option<&str>,
+  ) -> Result<(), ()> {
+    let doc_ptr = self.doc_ptr();
+    if doc_ptr.is_null() {
+      return Err(());
+    }
+    let c_value = value.map(CString::new).transpose().map_err(|_| ())?;
+    unsafe {
+      let dict = (*doc_ptr).dict;
+      let field = field(&mut *doc_ptr);
+      let old = *field;
+      *field = match &c_value {
+        Some(value) => xmlStrdup(value.as_bytes().as_ptr()),
+        None => ptr::null(),
+      };
+      if !old.is_null() && (dict.is_null() || xmlDictOwns(dict, old) == 0) {
+        bindgenFree(old as *mut c_void);
+      }
+    }
+    Ok(())
+  }
+
   fn ptr_as_result(&mut self, node_ptr: xmlNodePtr) -> Result<Node, ()> {
     if node_ptr.is_null() {
       Err(())
@@ -290,34 +517,8 @@ impl Document {
       // allocate a buffer to dump into
       let buf = xmlBufferCreate();
       let c_utf8 = CString::new("UTF-8").unwrap();
-      let mut xml_options = 0;
 
-      if options.format {
-        xml_options += xmlSaveOption_XML_SAVE_FORMAT;
-      }
-      if options.no_declaration {
-        xml_options += xmlSaveOption_XML_SAVE_NO_DECL;
-      }
-      if options.no_empty_tags {
-        xml_options += xmlSaveOption_XML_SAVE_NO_EMPTY;
-      }
-      if options.no_xhtml {
-        xml_options += xmlSaveOption_XML_SAVE_NO_XHTML;
-      }
-      if options.xhtml {
-        xml_options += xmlSaveOption_XML_SAVE_XHTML;
-      }
-      if options.as_xml {
-        xml_options += xmlSaveOption_XML_SAVE_AS_XML;
-      }
-      if options.as_html {
-        xml_options += xmlSaveOption_XML_SAVE_AS_HTML;
-      }
-      if options.non_significant_whitespace {
-        xml_options += xmlSaveOption_XML_SAVE_WSNONSIG;
-      }
-
-      let save_ctx = xmlSaveToBuffer(buf, c_utf8.as_ptr(), xml_options as i32);
+      let save_ctx = xmlSaveToBuffer(buf, c_utf8.as_ptr(), options.to_flags());
       let _size = xmlSaveDoc(save_ctx, self.doc_ptr());
       let _size = xmlSaveClose(save_ctx);
 
@@ -375,6 +576,13 @@ impl Document {
     }
   }
 
+  /// Resolve an XPointer against this document, returning the selected node set.
+  /// See `Context::evaluate_xpointer` for the supported schemes.
+  pub fn xpointer(&self, pointer: &str) -> Result<Object, XPointerError> {
+    let context = Context::new(self).map_err(|_| XPointerError::ContextCreationFailed)?;
+    context.evaluate_xpointer(pointer)
+  }
+
   /// Creates a node for an XML processing instruction
   pub fn create_processing_instruction(&mut self, name: &str, content: &str) -> Result<Node, ()> {
     unsafe {
@@ -406,6 +614,28 @@ impl Document {
     self.register_node(self.doc_ptr() as xmlNodePtr)
   }
 
+  /// Freeze the document into a `FrozenDocument`, which is `Send + Sync`
+  /// and can be read concurrently, e.g. with one `FrozenContext` per thread.
+  ///
+  /// This consumes the last handle on the document: if another `Document`
+  /// clone or any `Node` wrapper is still alive, the document is handed
+  /// back unchanged as `Err`. Use `FrozenDocument::thaw` to mutate again.
+  pub fn freeze(self) -> Result<FrozenDocument, Document> {
+    if self.0.borrow().has_live_nodes() {
+      return Err(self);
+    }
+    match Rc::try_unwrap(self.0) {
+      Ok(cell) => {
+        let mut inner = cell.into_inner();
+        let doc_ptr = inner.doc_ptr;
+        // the bookkeeping table is dropped with `inner`, the tree now belongs to the frozen handle
+        inner.doc_ptr = ptr::null_mut();
+        Ok(FrozenDocument::new_ptr(doc_ptr))
+      }
+      Err(doc_ref) => Err(Document(doc_ref)),
+    }
+  }
+
   /// Duplicates the libxml2 Document into a new instance
   pub fn dup(&self) -> Result<Self, ()> {
     let doc_ptr = unsafe { xmlCopyDoc(self.doc_ptr(), 1) };
@@ -435,4 +665,30 @@ impl Document {
   }
 }
 
+/// Whether `node` is part of the tree of `doc`, rather than unlinked from it
+pub(crate) fn is_in_document(node: xmlNodePtr, doc: xmlDocPtr) -> bool {
+  let mut ancestor = node;
+  unsafe {
+    while !(*ancestor).parent.is_null() {
+      ancestor = (*ancestor).parent;
+    }
+  }
+  ancestor == doc as xmlNodePtr
+}
+
+/// Copy a libxml2 string, `None` for NULL
+unsafe fn to_string_option(value: *const xmlChar) -> Option<String> {
+  if value.is_null() {
+    None
+  } else {
+    let c_string = unsafe { CStr::from_ptr(value as *const c_char) };
+    Some(c_string.to_string_lossy().into_owned())
+  }
+}
+
+mod adopt;
 mod c14n;
+mod serialize;
+
+pub(crate) use self::c14n::{canonicalize_into, Visibility};
+pub use self::serialize::OutputOptions;
@@ -0,0 +1,127 @@
+//! Moving nodes between documents
+//!
+use std::error::Error;
+use std::ffi::c_void;
+use std::ptr;
+
+use crate::bindings::{
+  xmlAddID, xmlAttrPtr, xmlDOMWrapAdoptNode, xmlDOMWrapFreeCtxt, xmlDOMWrapNewCtxt,
+  xmlDOMWrapReconcileNamespaces, xmlDocPtr, xmlIsID, xmlNodeListGetString, xmlNodePtr,
+};
+use crate::c_helpers::{
+  bindgenFree, xmlGetDoc, xmlGetFirstProperty, xmlGetNodeType, xmlNextPropertySibling,
+};
+use crate::readonly::RoNode;
+use crate::tree::nodetype::NodeType;
+use crate::tree::traversal::TraversalEvent;
+use crate::tree::{Document, Node};
+
+impl Document {
+  /// Move `node` and its subtree from its document into this one, without
+  /// copying (`xmlDOMWrapAdoptNode`). The node is unlinked from its tree;
+  /// attach it with `add_child`, `add_prev_sibling` or `add_next_sibling`.
+  ///
+  /// Namespaces the subtree uses but does not declare are declared on
+  /// `node`, and its IDs move to this document. The wrappers of `node` and
+  /// of its descendants stay valid and now belong to this document. After
+  /// attaching the node, `Node::reconcile_namespaces_with` can remove the
+  /// declarations its new ancestors make redundant.
+  ///
+  /// Returns an error for document nodes, for nodes owned by their Rust
+  /// wrapper (see `Node::set_rust_owned`), or if libxml2 fails.
+  pub fn adopt_node(&mut self, node: &mut Node) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match node.get_type() {
+      None | Some(NodeType::DocumentNode) | Some(NodeType::HtmlDocumentNode) => {
+        return Err(From::from("only nodes of a document tree can be adopted"));
+      }
+      _ => {}
+    }
+    if node.is_rust_owned() {
+      return Err(From::from("can not adopt a node owned by its Rust wrapper"));
+    }
+    let source = node
+      .get_docref()
+      .upgrade()
+      .ok_or("the node's document was dropped")?;
+    let node_ptr = node.node_ptr_mut()?;
+    let dest_doc = self.doc_ptr();
+    let status = unsafe {
+      let ctxt = xmlDOMWrapNewCtxt();
+      if ctxt.is_null() {
+        return Err(From::from("libxml2 could not create a DOM wrapper context"));
+      }
+      let mut status = xmlDOMWrapAdoptNode(
+        ctxt,
+        xmlGetDoc(node_ptr),
+        node_ptr,
+        dest_doc,
+        ptr::null_mut(),
+        0,
+      );
+      // without a parent, the namespaces of the subtree are left to the
+      // document rather than declared on the node
+      if status == 0 && node.get_type() == Some(NodeType::ElementNode) {
+        status = xmlDOMWrapReconcileNamespaces(ctxt, node_ptr, 0);
+      }
+      xmlDOMWrapFreeCtxt(ctxt);
+      status
+    };
+    if status != 0 {
+      return Err(From::from(format!(
+        "xmlDOMWrapAdoptNode failed with status: {status:?}"
+      )));
+    }
+
+    // move the wrappers of the subtree to the bookkeeping of this document
+    node.set_unlinked();
+    for descendant in subtree(node_ptr) {
+      let wrapper = source.borrow().get_node(descendant).cloned();
+      if let Some(wrapper) = wrapper {
+        source.borrow_mut().forget_node(descendant);
+        wrapper.set_document(&self.0);
+        self.0.borrow_mut().insert_node(descendant, wrapper);
+      }
+      register_id(dest_doc, descendant);
+    }
+    node.set_document(&self.0);
+    self.0.borrow_mut().insert_node(node_ptr, node.clone());
+    Ok(())
+  }
+}
+
+/// The nodes of the subtree of `root`, with their attributes
+fn subtree(root: xmlNodePtr) -> Vec<xmlNodePtr> {
+  let mut nodes = Vec::new();
+  for event in RoNode(root).traverse() {
+    if let TraversalEvent::Enter(node) = event {
+      if node.is_element_node() {
+        let mut attr = xmlGetFirstProperty(node.0);
+        while !attr.is_null() {
+          nodes.push(attr as xmlNodePtr);
+          attr = xmlNextPropertySibling(attr);
+        }
+      }
+      nodes.push(node.0);
+    }
+  }
+  nodes
+}
+
+/// Register the attribute `node` in the ID table of `doc` if it is an ID:
+/// libxml2 removes the IDs of adopted nodes from their former document only
+fn register_id(doc: xmlDocPtr, node: xmlNodePtr) {
+  if NodeType::from_int(xmlGetNodeType(node)) != Some(NodeType::AttributeNode) {
+    return;
+  }
+  unsafe {
+    let attr = node as xmlAttrPtr;
+    if xmlIsID(doc, (*attr).parent, attr) != 1 {
+      return;
+    }
+    let value = xmlNodeListGetString(doc, (*attr).children, 1);
+    if !value.is_null() {
+      xmlAddID(ptr::null_mut(), doc, value, attr);
+      bindgenFree(value as *mut c_void);
+    }
+  }
+}
@@ -1,14 +1,19 @@
 //! Document canonicalization logic
 //!
 use std::ffi::{c_int, c_void, CString};
-use std::os::raw;
+use std::io::{self, Write};
 use std::ptr::null_mut;
 
+use crate::c_helpers::xmlGetNodeType;
+use crate::readonly::RoNode;
 use crate::tree::c14n::*;
+use crate::xpath::{Context, ObjectType};
 
+use super::serialize::{close_callback, write_callback, WriteContext};
 use super::{
-  xmlAllocOutputBuffer, xmlC14NExecute, xmlC14NIsVisibleCallback, xmlChar, xmlNodePtr,
-  xmlOutputBufferClose, xmlOutputBufferPtr, Document,
+  xmlC14NDocSaveTo, xmlC14NExecute, xmlC14NIsVisibleCallback, xmlChar, xmlDocPtr,
+  xmlElementType_XML_NAMESPACE_DECL, xmlNodePtr, xmlNodeSetPtr, xmlOutputBufferClose,
+  xmlOutputBufferCreateIO, Document,
 };
 
 impl Document {
@@ -18,94 +23,217 @@ impl Document {
     options: CanonicalizationOptions,
     callback: Option<(xmlNodePtr, xmlC14NIsVisibleCallback)>,
   ) -> Result<String, ()> {
-    let document = (*self.0).borrow().doc_ptr;
-
-    let mut ns_list_c = to_xml_string_vec(options.inclusive_ns_prefixes);
-    let inclusive_ns_prefixes = ns_list_c.as_mut_ptr();
-    let with_comments = c_int::from(options.with_comments);
-
     let (is_visible_callback, user_data) = if let Some((node_ptr, visibility_callback)) = callback {
       (visibility_callback, node_ptr as *mut _)
     } else {
       (None, null_mut())
     };
 
-    let mode = options.mode.into();
-    unsafe {
-      let c_obuf = create_output_buffer();
-
-      let status = xmlC14NExecute(
-        document,
-        is_visible_callback,
-        user_data,
-        mode,
-        inclusive_ns_prefixes,
-        with_comments,
-        c_obuf,
-      );
+    let mut output = Vec::new();
+    canonicalize_into(
+      self.doc_ptr(),
+      options,
+      Visibility::Callback(is_visible_callback, user_data),
+      &mut output,
+    )
+    .map_err(|_| ())?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+  }
 
-      let res = c_obuf_into_output(c_obuf);
+  /// Canonicalize the part of the document selected by `filter` and return the results,
+  /// e.g. to apply the transforms of an XML-DSig reference.
+  pub fn canonicalize_filtered(
+    &self,
+    options: CanonicalizationOptions,
+    filter: CanonicalizationFilter,
+  ) -> Result<String, ()> {
+    let mut output = Vec::new();
+    self
+      .canonicalize_filtered_to(&mut output, options, filter)
+      .map_err(|_| ())?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+  }
 
-      if status < 0 {
-        Err(())
-      } else {
-        Ok(res)
+  /// Canonicalize the part of the document selected by `filter` into `writer`,
+  /// see `Document::canonicalize_to`.
+  ///
+  /// An XPath filter that does not evaluate to a node set is reported as
+  /// an `io::ErrorKind::InvalidInput` error.
+  pub fn canonicalize_filtered_to<W: Write>(
+    &self,
+    mut writer: W,
+    options: CanonicalizationOptions,
+    filter: CanonicalizationFilter,
+  ) -> io::Result<()> {
+    match filter {
+      CanonicalizationFilter::Visible(visible) => {
+        let mut visible: &dyn Fn(RoNode, Option<RoNode>) -> bool = visible;
+        let user_data = &mut visible as *mut &dyn Fn(RoNode, Option<RoNode>) -> bool;
+        canonicalize_into(
+          self.doc_ptr(),
+          options,
+          Visibility::Callback(Some(visible_trampoline), user_data as *mut c_void),
+          &mut writer,
+        )
+      }
+      CanonicalizationFilter::XPath {
+        expression,
+        namespaces,
+      } => {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let context =
+          Context::new(self).map_err(|_| io::Error::other("could not create an XPath context"))?;
+        for (prefix, href) in namespaces {
+          context
+            .register_namespace(prefix, href)
+            .map_err(|_| invalid("could not register a namespace"))?;
+        }
+        let object = context
+          .evaluate(expression)
+          .map_err(|_| invalid("invalid XPath expression"))?;
+        if object.get_type() != ObjectType::NodeSet {
+          return Err(invalid("the XPath expression does not select a node set"));
+        }
+        let nodes = unsafe { (*object.ptr).nodesetval };
+        canonicalize_into(
+          self.doc_ptr(),
+          options,
+          Visibility::NodeSet(nodes),
+          &mut writer,
+        )
       }
     }
   }
-}
-
-unsafe fn c_obuf_into_output(c_obuf: xmlOutputBufferPtr) -> String { unsafe {
-  let ctx_ptr = (*c_obuf).context;
-  let output = Box::from_raw(ctx_ptr as *mut String);
 
-  (*c_obuf).context = std::ptr::null_mut::<c_void>();
-
-  xmlOutputBufferClose(c_obuf);
-
-  *output
-}}
+  /// Canonicalize a document into `writer`, without building the output in memory.
+  ///
+  /// The first error returned by `writer` aborts the canonicalization and is
+  /// returned unchanged.
+  pub fn canonicalize_to<W: Write>(
+    &self,
+    mut writer: W,
+    options: CanonicalizationOptions,
+  ) -> io::Result<()> {
+    canonicalize_into(self.doc_ptr(), options, Visibility::All, &mut writer)
+  }
 
-unsafe fn create_output_buffer() -> xmlOutputBufferPtr { unsafe {
-  let output = String::new();
-  let ctx_ptr = Box::into_raw(Box::new(output));
-  let encoder = std::ptr::null_mut();
+  /// Canonicalize a document and return the resulting bytes
+  pub fn canonicalize_to_bytes(&self, options: CanonicalizationOptions) -> Result<Vec<u8>, ()> {
+    let mut output = Vec::new();
+    self.canonicalize_to(&mut output, options).map_err(|_| ())?;
+    Ok(output)
+  }
+}
 
-  let buf = xmlAllocOutputBuffer(encoder);
+/// Selects the nodes of a document that end up in the canonical form
+pub(crate) enum Visibility {
+  /// the whole document
+  All,
+  /// nodes accepted by a libxml2 visibility callback and its user data
+  Callback(xmlC14NIsVisibleCallback, *mut c_void),
+  /// the nodes of an XPath node set (which may be empty or NULL)
+  NodeSet(xmlNodeSetPtr),
+}
 
-  (*buf).writecallback = Some(xml_write_io);
-  (*buf).closecallback = Some(xml_close_io);
-  (*buf).context = ctx_ptr as _;
+/// Canonicalize `document`, streaming the output into `writer`
+pub(crate) fn canonicalize_into(
+  document: xmlDocPtr,
+  options: CanonicalizationOptions,
+  visibility: Visibility,
+  writer: &mut dyn Write,
+) -> io::Result<()> {
+  let ns_list = to_xml_strings(options.inclusive_ns_prefixes)?;
+  let mut ns_list_c: Vec<*mut xmlChar> = ns_list
+    .iter()
+    .map(|prefix| prefix.as_ptr() as *mut xmlChar)
+    .chain(std::iter::once(null_mut()))
+    .collect();
+  let with_comments = c_int::from(options.with_comments);
+  let mode = options.mode.into();
+
+  let mut output = WriteContext::new(writer);
+  unsafe {
+    let c_obuf = xmlOutputBufferCreateIO(
+      Some(write_callback),
+      Some(close_callback),
+      output.as_ptr(),
+      null_mut(),
+    );
+    if c_obuf.is_null() {
+      return Err(io::Error::other("libxml2 could not create an output buffer"));
+    }
 
-  buf
-}}
+    let inclusive_ns_prefixes = ns_list_c.as_mut_ptr();
+    let status = match visibility {
+      Visibility::All => xmlC14NExecute(
+        document,
+        None,
+        null_mut(),
+        mode,
+        inclusive_ns_prefixes,
+        with_comments,
+        c_obuf,
+      ),
+      Visibility::Callback(is_visible_callback, user_data) => xmlC14NExecute(
+        document,
+        is_visible_callback,
+        user_data,
+        mode,
+        inclusive_ns_prefixes,
+        with_comments,
+        c_obuf,
+      ),
+      // an empty selection canonicalizes to nothing, while libxml2 treats
+      // a NULL node set as the whole document
+      Visibility::NodeSet(nodes) if nodes.is_null() || (*nodes).nodeNr == 0 => 0,
+      Visibility::NodeSet(nodes) => xmlC14NDocSaveTo(
+        document,
+        nodes,
+        mode,
+        inclusive_ns_prefixes,
+        with_comments,
+        c_obuf,
+      ),
+    };
+    let closed = xmlOutputBufferClose(c_obuf);
 
-unsafe extern "C" fn xml_close_io(_context: *mut raw::c_void) -> raw::c_int {
-  0
+    output.finish(status >= 0 && closed >= 0)
+  }
 }
 
-unsafe extern "C" fn xml_write_io(
-  io_ptr: *mut raw::c_void,
-  buffer: *const raw::c_char,
-  len: raw::c_int,
-) -> raw::c_int { unsafe {
-  if io_ptr.is_null() {
-    0
-  } else {
-    let buf = std::slice::from_raw_parts_mut(buffer as *mut u8, len as usize);
-    let buf = String::from_utf8_lossy(buf);
-    let s2_ptr = io_ptr as *mut String;
-    String::push_str(&mut *s2_ptr, &buf);
-
-    len
+/// Calls the `&dyn Fn` behind `user_data` for `CanonicalizationFilter::Visible`
+unsafe extern "C" fn visible_trampoline(
+  user_data: *mut c_void,
+  node_ptr: xmlNodePtr,
+  parent_ptr: xmlNodePtr,
+) -> c_int {
+  // SAFETY: `user_data` points at the closure reference on the stack of
+  // `canonicalize_filtered_to`, which outlives the canonicalization.
+  let visible = unsafe { *(user_data as *const &dyn Fn(RoNode, Option<RoNode>) -> bool) };
+  if node_ptr.is_null() {
+    return 0;
   }
-}}
+  let parent = (!parent_ptr.is_null()).then_some(RoNode(parent_ptr));
+  let accepted = if xmlGetNodeType(node_ptr) == xmlElementType_XML_NAMESPACE_DECL {
+    // `node_ptr` is an `xmlNs`, which can not be exposed as a node:
+    // namespace declarations follow the element they apply to
+    match parent {
+      Some(element) => visible(element, element.get_parent()),
+      None => false,
+    }
+  } else {
+    visible(RoNode(node_ptr), parent)
+  };
+  c_int::from(accepted)
+}
 
-/// Create a [Vec] of null-terminated [*mut xmlChar] strings
-fn to_xml_string_vec(vec: Vec<String>) -> Vec<*mut xmlChar> {
+/// The prefixes as C strings, kept alive by the caller for the duration of the call
+fn to_xml_strings(vec: Vec<String>) -> io::Result<Vec<CString>> {
   vec
     .into_iter()
-    .map(|s| CString::new(s).unwrap().into_raw() as *mut xmlChar)
-    .chain(std::iter::once(std::ptr::null_mut()))
+    .map(|s| {
+      CString::new(s)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "prefix contains a NUL byte"))
+    })
     .collect()
 }
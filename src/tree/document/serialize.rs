@@ -0,0 +1,346 @@
+//! Document serialization into `std::io::Write`
+//!
+use std::ffi::{c_char, c_int, c_long, c_void, CString};
+use std::io::{self, Write};
+
+use crate::bindings::{
+  __xmlTreeIndentString, xmlCharEncCloseFunc, xmlFindCharEncodingHandler, xmlSaveClose,
+  xmlSaveCtxtPtr, xmlSaveDoc, xmlSaveToFilename, xmlSaveToIO, xmlSaveTree, xmlNodePtr,
+};
+use crate::readonly::RoNode;
+use crate::tree::{Document, Node, SaveOptions};
+
+#[derive(Debug, Copy, Clone, Default)]
+/// Output settings for the byte-level serializers, on top of `SaveOptions`
+pub struct OutputOptions<'a> {
+  /// libxml2 save flags
+  pub save: SaveOptions,
+  /// string emitted once per nesting level when `save.format` is set,
+  /// two spaces when `None`. libxml2 caps the indentation of a line at 60 bytes.
+  pub indent: Option<&'a str>,
+  /// target encoding of the output (e.g. "ISO-8859-1", "UTF-16"), UTF-8 when `None`
+  pub encoding: Option<&'a str>,
+  /// without `encoding`, encode documents in their declared encoding (see
+  /// `Document::get_encoding`) rather than UTF-8. Nodes stay in UTF-8.
+  pub declared_encoding: bool,
+}
+
+impl<'a> OutputOptions<'a> {
+  /// These options, with the declared encoding `declared` if they opt in to it
+  fn or_declared(&self, declared: Option<&'a str>) -> Self {
+    OutputOptions {
+      encoding: self
+        .encoding
+        .or(declared.filter(|_| self.declared_encoding)),
+      ..*self
+    }
+  }
+}
+
+impl Document {
+  /// Serializes the `Document` with options into `writer`, without first
+  /// building the output in memory.
+  ///
+  /// The first error returned by `writer` aborts the serialization and is
+  /// returned unchanged.
+  pub fn write_to<W: Write>(&self, writer: W, options: SaveOptions) -> io::Result<()> {
+    let doc_ptr = self.doc_ptr();
+    let options = OutputOptions {
+      save: options,
+      ..OutputOptions::default()
+    };
+    save_to_writer(writer, &options, |ctxt| unsafe { xmlSaveDoc(ctxt, doc_ptr) })
+  }
+
+  /// Serializes a `Node` owned by this `Document` into `writer`, see `Document::write_to`
+  pub fn write_node_to<W: Write>(
+    &self,
+    node: &Node,
+    writer: W,
+    options: SaveOptions,
+  ) -> io::Result<()> {
+    let options = OutputOptions {
+      save: options,
+      ..OutputOptions::default()
+    };
+    save_node_to_writer(node.node_ptr(), writer, &options)
+  }
+
+  /// Serializes a `RoNode` owned by this `Document` into `writer`, see `Document::write_to`
+  pub fn write_ronode_to<W: Write>(
+    &self,
+    node: &RoNode,
+    writer: W,
+    options: SaveOptions,
+  ) -> io::Result<()> {
+    let options = OutputOptions {
+      save: options,
+      ..OutputOptions::default()
+    };
+    save_node_to_writer(node.node_ptr(), writer, &options)
+  }
+
+  /// Save the `Document` to `filename` with `options`, through libxml2's output
+  /// handlers: a URL claimed by `io::register_output_callback` is delivered to
+  /// its closure, anything else is written by libxml2's default file handler.
+  ///
+  /// An error returned by the claiming closure is returned unchanged.
+  pub fn save_file_with_options(&self, filename: &str, options: &OutputOptions) -> io::Result<()> {
+    let c_filename = CString::new(filename)
+      .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "filename contains a NUL byte"))?;
+    let declared = self.get_encoding();
+    let options = &options.or_declared(declared.as_deref());
+    let save_ctx = new_save_context(options, |encoding, flags| unsafe {
+      xmlSaveToFilename(c_filename.as_ptr(), encoding, flags)
+    })
+    .map_err(|error| match error.kind() {
+      io::ErrorKind::InvalidInput => error,
+      _ => io::Error::other(format!("libxml2 could not open {filename:?} for writing")),
+    })?;
+    crate::io::take_output_error();
+    let (written, closed) = unsafe {
+      let written = xmlSaveDoc(save_ctx, self.doc_ptr());
+      (written, xmlSaveClose(save_ctx))
+    };
+    match crate::io::take_output_error() {
+      Some(error) => Err(error),
+      None if written < 0 || closed < 0 => Err(io::Error::other(format!(
+        "libxml2 failed to save {filename:?}"
+      ))),
+      None => Ok(()),
+    }
+  }
+
+  /// Serializes the `Document` into bytes in the requested encoding, with
+  /// the indentation string and save flags of `options`.
+  ///
+  /// Returns an `io::ErrorKind::InvalidInput` error if libxml2 has no
+  /// converter for the encoding.
+  pub fn to_bytes_with_options(&self, options: &OutputOptions) -> io::Result<Vec<u8>> {
+    let doc_ptr = self.doc_ptr();
+    let declared = self.get_encoding();
+    let options = &options.or_declared(declared.as_deref());
+    let mut output = Vec::new();
+    save_to_writer(&mut output, options, |ctxt| unsafe {
+      xmlSaveDoc(ctxt, doc_ptr)
+    })?;
+    Ok(output)
+  }
+
+  /// Serializes a `Node` owned by this `Document` into bytes, see `Document::to_bytes_with_options`
+  pub fn node_to_bytes_with_options(
+    &self,
+    node: &Node,
+    options: &OutputOptions,
+  ) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    save_node_to_writer(node.node_ptr(), &mut output, options)?;
+    Ok(output)
+  }
+
+  /// Serializes a `RoNode` owned by this `Document` into bytes, see `Document::to_bytes_with_options`
+  pub fn ronode_to_bytes_with_options(
+    &self,
+    node: &RoNode,
+    options: &OutputOptions,
+  ) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    save_node_to_writer(node.node_ptr(), &mut output, options)?;
+    Ok(output)
+  }
+
+  /// Serializes a `Node` owned by this `Document` with options.
+  /// Unlike `node_to_string`, formatting and the other save flags are honored.
+  /// Fails as `node_to_bytes_with_options` does.
+  pub fn node_to_string_with_options(
+    &self,
+    node: &Node,
+    options: SaveOptions,
+  ) -> io::Result<String> {
+    let options = OutputOptions {
+      save: options,
+      ..OutputOptions::default()
+    };
+    utf8_string(self.node_to_bytes_with_options(node, &options)?)
+  }
+
+  /// Serializes a `RoNode` owned by this `Document` with options, see `Document::node_to_string_with_options`
+  pub fn ronode_to_string_with_options(
+    &self,
+    node: &RoNode,
+    options: SaveOptions,
+  ) -> io::Result<String> {
+    let options = OutputOptions {
+      save: options,
+      ..OutputOptions::default()
+    };
+    utf8_string(self.ronode_to_bytes_with_options(node, &options)?)
+  }
+}
+
+/// The UTF-8 output of a serializer as a `String`
+fn utf8_string(output: Vec<u8>) -> io::Result<String> {
+  String::from_utf8(output).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+fn save_node_to_writer<W: Write>(
+  node_ptr: xmlNodePtr,
+  writer: W,
+  options: &OutputOptions,
+) -> io::Result<()> {
+  save_to_writer(writer, options, |ctxt| unsafe { xmlSaveTree(ctxt, node_ptr) })
+}
+
+/// Run `save` on a libxml2 save context whose output goes to `writer`
+fn save_to_writer<W, F>(mut writer: W, options: &OutputOptions, save: F) -> io::Result<()>
+where
+  W: Write,
+  F: FnOnce(xmlSaveCtxtPtr) -> c_long,
+{
+  let mut output = WriteContext::new(&mut writer);
+  let save_ctx = new_save_context(options, |encoding, flags| unsafe {
+    xmlSaveToIO(
+      Some(write_callback),
+      Some(close_callback),
+      output.as_ptr(),
+      encoding,
+      flags,
+    )
+  })?;
+  unsafe {
+    let written = save(save_ctx);
+    let closed = xmlSaveClose(save_ctx);
+    output.finish(written >= 0 && closed >= 0)
+  }
+}
+
+/// Validate the encoding and indent of `options`, then `create` a save
+/// context from the encoding name and save flags with the indent in effect.
+fn new_save_context<F>(options: &OutputOptions, create: F) -> io::Result<xmlSaveCtxtPtr>
+where
+  F: FnOnce(*const c_char, c_int) -> xmlSaveCtxtPtr,
+{
+  let encoding = options.encoding.unwrap_or("UTF-8");
+  let c_encoding = CString::new(encoding)
+    .ok()
+    .filter(is_known_encoding)
+    .ok_or_else(|| {
+      io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("unsupported output encoding {encoding:?}"),
+      )
+    })?;
+  let c_indent = options
+    .indent
+    .map(CString::new)
+    .transpose()
+    .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "indent contains a NUL byte"))?;
+
+  let save_ctx = unsafe {
+    // The save context copies libxml2's (thread-local) indent string when it is
+    // created, so it only needs to be swapped in for the call to `create`.
+    let indent_string = __xmlTreeIndentString();
+    let default_indent = *indent_string;
+    if let Some(c_indent) = &c_indent {
+      *indent_string = c_indent.as_ptr();
+    }
+    let save_ctx = create(c_encoding.as_ptr(), options.save.to_flags());
+    *indent_string = default_indent;
+    save_ctx
+  };
+  if save_ctx.is_null() {
+    Err(io::Error::other("libxml2 could not create a save context"))
+  } else {
+    Ok(save_ctx)
+  }
+}
+
+/// Whether libxml2 can convert its output to `encoding`
+fn is_known_encoding(encoding: &CString) -> bool {
+  unsafe {
+    let handler = xmlFindCharEncodingHandler(encoding.as_ptr());
+    if handler.is_null() {
+      false
+    } else {
+      xmlCharEncCloseFunc(handler);
+      true
+    }
+  }
+}
+
+/// The `void *` context handed to libxml2's output callbacks: the Rust
+/// writer and the first error it returned, which libxml2 itself can only
+/// record as a failed write.
+pub(crate) struct WriteContext<'a> {
+  writer: &'a mut dyn Write,
+  error: Option<io::Error>,
+}
+
+impl<'a> WriteContext<'a> {
+  pub(crate) fn new(writer: &'a mut dyn Write) -> Self {
+    WriteContext {
+      writer,
+      error: None,
+    }
+  }
+
+  /// Pointer to pass as the context of `write_callback` and `close_callback`.
+  /// Only valid while `self` is neither moved nor dropped.
+  pub(crate) fn as_ptr(&mut self) -> *mut c_void {
+    self as *mut WriteContext as *mut c_void
+  }
+
+  /// The writer's own error if it failed, otherwise a generic error if libxml2 did
+  pub(crate) fn finish(mut self, libxml_ok: bool) -> io::Result<()> {
+    match self.error.take() {
+      Some(error) => Err(error),
+      None if !libxml_ok => Err(io::Error::other("libxml2 failed to serialize the output")),
+      None => Ok(()),
+    }
+  }
+}
+
+pub(crate) unsafe extern "C" fn write_callback(
+  context: *mut c_void,
+  buffer: *const c_char,
+  len: c_int,
+) -> c_int {
+  if context.is_null() || len < 0 {
+    return -1;
+  }
+  // SAFETY: `context` comes from `WriteContext::as_ptr`, which outlives the save context.
+  let output = unsafe { &mut *(context as *mut WriteContext) };
+  if output.error.is_some() {
+    return -1;
+  }
+  let bytes = if len == 0 {
+    &[][..]
+  } else {
+    unsafe { std::slice::from_raw_parts(buffer as *const u8, len as usize) }
+  };
+  match output.writer.write_all(bytes) {
+    Ok(()) => len,
+    Err(error) => {
+      output.error = Some(error);
+      -1
+    }
+  }
+}
+
+pub(crate) unsafe extern "C" fn close_callback(context: *mut c_void) -> c_int {
+  if context.is_null() {
+    return -1;
+  }
+  // SAFETY: see `write_callback`.
+  let output = unsafe { &mut *(context as *mut WriteContext) };
+  if output.error.is_some() {
+    return -1;
+  }
+  match output.writer.flush() {
+    Ok(()) => 0,
+    Err(error) => {
+      output.error = Some(error);
+      -1
+    }
+  }
+}
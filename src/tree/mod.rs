@@ -3,10 +3,12 @@ pub mod document;
 pub mod namespace;
 pub mod node;
 pub mod nodetype;
+pub mod traversal;
 
-pub use self::document::{Document, SaveOptions};
+pub use self::document::{Doctype, Document, OutputOptions, SaveOptions};
 pub(crate) use self::document::{DocumentRef, DocumentWeak};
 pub use self::namespace::Namespace;
 pub use self::node::set_node_rc_guard;
-pub use self::node::{Node, NODE_RC_MAX_GUARD};
+pub use self::node::{Node, ReconcileOptions, NODE_RC_MAX_GUARD};
 pub use self::nodetype::NodeType;
+pub use self::traversal::{TraversalEvent, WalkAction};
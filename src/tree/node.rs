@@ -1,7 +1,8 @@
 //! Node, and related, feature set
 //!
-use libc::{c_char, c_void};
+use libc::{c_char, c_int, c_void};
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::ffi::{CStr, CString};
@@ -11,9 +12,12 @@ use std::rc::Rc;
 use std::str;
 
 use crate::bindings::*;
+use crate::css::SelectorError;
 use crate::c_helpers::*;
+use crate::readonly::RoNode;
 use crate::tree::namespace::Namespace;
 use crate::tree::nodetype::NodeType;
+use crate::tree::document::is_in_document;
 use crate::tree::{Document, DocumentRef, DocumentWeak};
 use crate::xpath::Context;
 
@@ -40,7 +44,9 @@ type NodeRef = Rc<RefCell<_Node>>;
 /// * `Unlinked` — `xmlUnlinkNode` has detached the node from its
 ///   parent/siblings, but `node->doc` still points at the source xmlDoc.
 ///   The source still owns the C allocation. The wrapper must not free
-///   unless `node->doc` itself is NULL (a true C-level orphan).
+///   unless `node->doc` itself is NULL (a true C-level orphan). Nodes
+///   created unlinked (`Node::new`, `Node::copy`, ...) and never attached
+///   are freed by their document instead, see `free_if_unattached`.
 /// * `RustOwned` — the caller has explicitly transferred ownership to
 ///   the Rust wrapper via `Node::set_rust_owned`. The C allocation will
 ///   be freed via `xmlFreeNode` when the last `Node` clone drops,
@@ -64,6 +70,9 @@ struct _Node {
   document: DocumentWeak,
   /// Lifecycle state — see `Linkage` for the semantics of each variant.
   linkage: Linkage,
+  /// Created by `Node::new_ns`, not yet attached: use a declaration of its
+  /// namespace in scope once attached
+  ns_on_attach: bool,
 }
 
 /// An xml node
@@ -170,6 +179,48 @@ impl Node {
     }
   }
 
+  /// Create a new element in the namespace `namespace_uri`, bound to a given
+  /// document, as DOM's `createElementNS`. The element declares the
+  /// namespace itself, with `prefix_hint` as prefix or as the default
+  /// namespace for `None`. When it is first attached with `add_child`,
+  /// `add_prev_sibling` or `add_next_sibling`, a declaration of
+  /// `namespace_uri` in scope there is used instead, and its prefix.
+  ///
+  /// An empty `namespace_uri` creates an element in no namespace.
+  pub fn new_ns(
+    local_name: &str,
+    namespace_uri: &str,
+    prefix_hint: Option<&str>,
+    doc: &Document,
+  ) -> Result<Self, ()> {
+    let node = Node::new(local_name, None, doc)?;
+    if namespace_uri.is_empty() {
+      return Ok(node);
+    }
+    let c_uri = CString::new(namespace_uri).map_err(|_| ())?;
+    let c_prefix = prefix_hint
+      .filter(|prefix| !prefix.is_empty())
+      .map(CString::new)
+      .transpose()
+      .map_err(|_| ())?;
+    let node_ptr = node.node_ptr();
+    unsafe {
+      let ns = xmlNewNs(
+        node_ptr,
+        c_uri.as_bytes().as_ptr(),
+        c_prefix
+          .as_ref()
+          .map_or(ptr::null(), |prefix| prefix.as_bytes().as_ptr()),
+      );
+      if ns.is_null() {
+        return Err(());
+      }
+      xmlSetNs(node_ptr, ns);
+    }
+    node.0.borrow_mut().ns_on_attach = true;
+    Ok(node)
+  }
+
   /// Immutably borrows the underlying libxml2 `xmlNodePtr` pointer
   pub fn node_ptr(&self) -> xmlNodePtr {
     self.0.borrow().node_ptr
@@ -213,6 +264,7 @@ impl Node {
       } else {
         Linkage::Linked
       },
+      ns_on_attach: false,
     };
     let wrapped_node = Node(Rc::new(RefCell::new(node)));
     document
@@ -260,6 +312,99 @@ impl Node {
       }
     }
   }
+
+  /// Create a new CDATA section node, bound to a given document. The
+  /// content is serialized as is, inside `<![CDATA[...]]>`.
+  ///
+  /// Returns `Err(())` if the content contains an embedded NUL, or if
+  /// libxml2 returns NULL.
+  pub fn new_cdata(content: &str, doc: &Document) -> Result<Self, ()> {
+    let c_content = CString::new(content).map_err(|_| ())?;
+    let length = c_int::try_from(content.len()).map_err(|_| ())?;
+    unsafe {
+      let node = xmlNewCDataBlock(doc.doc_ptr(), c_content.as_bytes().as_ptr(), length);
+      if node.is_null() {
+        Err(())
+      } else {
+        Ok(Node::wrap_new(node, &doc.0))
+      }
+    }
+  }
+
+  /// Create a new entity reference node, bound to a given document and
+  /// serialized as `&name;`. The surrounding `&` and `;` of `name` are
+  /// optional. The reference is resolved against the entities declared in
+  /// the document, but the entity does not need to be declared.
+  ///
+  /// Returns `Err(())` if the name contains an embedded NUL, or if libxml2
+  /// returns NULL.
+  pub fn new_entity_ref(name: &str, doc: &Document) -> Result<Self, ()> {
+    let c_name = CString::new(name).map_err(|_| ())?;
+    unsafe {
+      let node = xmlNewReference(doc.doc_ptr(), c_name.as_bytes().as_ptr());
+      if node.is_null() {
+        Err(())
+      } else {
+        Ok(Node::wrap_new(node, &doc.0))
+      }
+    }
+  }
+
+  /// Create a new, empty document fragment, bound to a given document.
+  /// Build content under it with `add_child`, then insert it with
+  /// `add_child`, `add_prev_sibling` or `add_next_sibling`: its children
+  /// are moved to the insertion point, leaving the fragment empty. Text
+  /// children are merged with the text nodes next to them, which empties
+  /// the wrappers of the merged children.
+  pub fn new_fragment(doc: &Document) -> Result<Self, ()> {
+    unsafe {
+      let node = xmlNewDocFragment(doc.doc_ptr());
+      if node.is_null() {
+        Err(())
+      } else {
+        Ok(Node::wrap_new(node, &doc.0))
+      }
+    }
+  }
+
+  /// Copy this node (`xmlCopyNode`) with its attributes and namespace
+  /// declarations and, if `deep`, its descendants. The copy belongs to the
+  /// same document but is unlinked — attach it via `add_child`,
+  /// `add_prev_sibling` or `add_next_sibling`. A copy that is never
+  /// attached is freed along with its document.
+  ///
+  /// Returns `Err(())` for document nodes, or if libxml2 returns NULL.
+  pub fn copy(&self, deep: bool) -> Result<Node, ()> {
+    self.check_copyable()?;
+    let document = self.get_docref().upgrade().ok_or(())?;
+    let copy_ptr = unsafe { xmlCopyNode(self.node_ptr(), copy_depth(deep)) };
+    if copy_ptr.is_null() {
+      Err(())
+    } else {
+      Ok(Node::wrap_new(copy_ptr, &document))
+    }
+  }
+
+  /// Copy this node into `doc` (`xmlDocCopyNode`), as for `copy`. Namespaces
+  /// declared outside of the copied subtree are redeclared on the copy.
+  pub fn copy_into(&self, doc: &Document, deep: bool) -> Result<Node, ()> {
+    self.check_copyable()?;
+    let copy_ptr = unsafe { xmlDocCopyNode(self.node_ptr(), doc.doc_ptr(), copy_depth(deep)) };
+    if copy_ptr.is_null() {
+      Err(())
+    } else {
+      Ok(Node::wrap_new(copy_ptr, &doc.0))
+    }
+  }
+
+  /// libxml2 copies document nodes as whole documents, which are not nodes we can track
+  fn check_copyable(&self) -> Result<(), ()> {
+    match self.get_type() {
+      None | Some(NodeType::DocumentNode) | Some(NodeType::HtmlDocumentNode) => Err(()),
+      _ => Ok(()),
+    }
+  }
+
   /// Create a mock node, used for a placeholder argument
   pub fn mock(doc: &Document) -> Self {
     Node::new("mock", None, doc).unwrap()
@@ -271,14 +416,39 @@ impl Node {
       node_ptr: ptr::null_mut(),
       document: Rc::downgrade(&Document::null_ref()),
       linkage: Linkage::Unlinked,
+      ns_on_attach: false,
     })))
   }
 
+  /// Compare the positions of two nodes in their document (`xmlXPathCmpNodes`).
+  /// Returns `None` for nodes that are not part of the same tree.
+  pub fn cmp_document_order(&self, other: &Node) -> Option<Ordering> {
+    RoNode(self.node_ptr()).cmp_document_order(RoNode(other.node_ptr()))
+  }
+
+  /// A total order for sorting, see `RoNode::document_order`
+  pub fn document_order(&self, other: &Node) -> Ordering {
+    RoNode(self.node_ptr()).document_order(RoNode(other.node_ptr()))
+  }
+
+  /// Sort `nodes` in document order and remove duplicates, e.g. to merge
+  /// the results of several `findnodes` calls
+  pub fn sort_in_document_order(nodes: &mut Vec<Node>) {
+    nodes.sort_by(|a, b| a.document_order(b));
+    nodes.dedup();
+  }
+
   /// `libc::c_void` isn't hashable and cannot be made hashable
   pub fn to_hashable(&self) -> usize {
     self.node_ptr() as usize
   }
 
+  /// Number of live `Node` clones sharing this wrapper, including the
+  /// one held by the owning document's bookkeeping table
+  pub(crate) fn wrapper_count(&self) -> usize {
+    Rc::strong_count(&self.0)
+  }
+
   pub(crate) fn get_docref(&self) -> DocumentWeak {
     self.0.borrow().document.clone()
   }
@@ -431,11 +601,19 @@ impl Node {
     &mut self,
     new_sibling: &mut Node,
   ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if new_sibling.is_fragment() {
+      let node_ptr = self.node_ptr_mut()?;
+      if self.get_type() == Some(NodeType::AttributeNode) {
+        return Err(From::from("can not insert a fragment next to an attribute"));
+      }
+      return Node::insert_fragment(new_sibling, xmlGetParent(node_ptr), node_ptr);
+    }
     new_sibling.set_linked();
     unsafe {
       if xmlAddPrevSibling(self.node_ptr_mut()?, new_sibling.node_ptr_mut()?).is_null() {
         Err(From::from("add_prev_sibling returned NULL"))
       } else {
+        new_sibling.use_namespace_in_scope();
         Ok(())
       }
     }
@@ -446,11 +624,23 @@ impl Node {
     &mut self,
     new_sibling: &mut Node,
   ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if new_sibling.is_fragment() {
+      let node_ptr = self.node_ptr_mut()?;
+      if self.get_type() == Some(NodeType::AttributeNode) {
+        return Err(From::from("can not insert a fragment next to an attribute"));
+      }
+      return Node::insert_fragment(
+        new_sibling,
+        xmlGetParent(node_ptr),
+        xmlNextSibling(node_ptr),
+      );
+    }
     new_sibling.set_linked();
     unsafe {
       if xmlAddNextSibling(self.node_ptr_mut()?, new_sibling.node_ptr_mut()?).is_null() {
         Err(From::from("add_next_sibling returned NULL"))
       } else {
+        new_sibling.use_namespace_in_scope();
         Ok(())
       }
     }
@@ -502,6 +692,27 @@ impl Node {
     rust_utf8
   }
 
+  /// Returns the line of the source document the node was parsed from,
+  /// or `None` for nodes created programmatically. Lines above 65535 are
+  /// only known for documents parsed with `ParserOptions::big_lines`; the
+  /// others report 65535.
+  pub fn get_line_number(&self) -> Option<usize> {
+    let line = unsafe { xmlGetLineNo(self.node_ptr()) };
+    usize::try_from(line).ok().filter(|line| *line > 0)
+  }
+
+  /// Returns an XPath-like path to the node, e.g. `/root/item[2]/@id`
+  pub fn get_path(&self) -> Option<String> {
+    let path_ptr = unsafe { xmlGetNodePath(self.node_ptr()) };
+    if path_ptr.is_null() {
+      return None;
+    }
+    let c_string = unsafe { CStr::from_ptr(path_ptr as *const c_char) };
+    let path = c_string.to_string_lossy().into_owned();
+    bindgenFree(path_ptr as *mut c_void);
+    Some(path)
+  }
+
   /// Sets the text content of this `Node`
   pub fn set_content(&mut self, content: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
     let c_content = CString::new(content).unwrap();
@@ -637,13 +848,14 @@ impl Node {
   ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let c_name = CString::new(name).unwrap();
     let c_value = CString::new(value).unwrap();
-    unsafe {
+    let attr = unsafe {
       xmlSetProp(
         self.node_ptr_mut()?,
         c_name.as_bytes().as_ptr(),
         c_value.as_bytes().as_ptr(),
       )
     };
+    self.sync_id(attr);
     Ok(())
   }
   /// Sets a namespaced attribute
@@ -655,7 +867,7 @@ impl Node {
   ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let c_name = CString::new(name).unwrap();
     let c_value = CString::new(value).unwrap();
-    unsafe {
+    let attr = unsafe {
       xmlSetNsProp(
         self.node_ptr_mut()?,
         ns.ns_ptr(),
@@ -663,6 +875,7 @@ impl Node {
         c_value.as_bytes().as_ptr(),
       )
     };
+    self.sync_id(attr);
     Ok(())
   }
 
@@ -790,6 +1003,50 @@ impl Node {
     self.set_property_ns(name, value, ns)
   }
 
+  /// Sets the attribute `local_name` in the namespace `namespace_uri`, as
+  /// DOM's `setAttributeNS`. A prefix declared for the namespace in scope is
+  /// reused, otherwise the namespace is declared on this node with a
+  /// generated prefix (`ns0`, `ns1`, ...). An empty `namespace_uri` sets an
+  /// attribute in no namespace.
+  pub fn set_attribute_ns_uri(
+    &mut self,
+    namespace_uri: &str,
+    local_name: &str,
+    value: &str,
+  ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let c_uri = CString::new(namespace_uri)?;
+    let c_name = CString::new(local_name)?;
+    let c_value = CString::new(value)?;
+    let node_ptr = self.node_ptr_mut()?;
+    if self.get_type() != Some(NodeType::ElementNode) {
+      return Err(From::from("attributes can only be set on elements"));
+    }
+    let attr = unsafe {
+      let ns = if namespace_uri.is_empty() {
+        ptr::null_mut()
+      } else {
+        let uri = c_uri.as_bytes().as_ptr();
+        let ns = reconcile::prefixed_ns_in_scope(node_ptr, uri);
+        if ns.is_null() {
+          reconcile::declare_generated_prefix(node_ptr, uri)
+        } else {
+          ns
+        }
+      };
+      xmlSetNsProp(
+        node_ptr,
+        ns,
+        c_name.as_bytes().as_ptr(),
+        c_value.as_bytes().as_ptr(),
+      )
+    };
+    if attr.is_null() {
+      return Err(From::from("xmlSetNsProp returned NULL"));
+    }
+    self.sync_id(attr);
+    Ok(())
+  }
+
   /// Alias for remove_property
   pub fn remove_attribute(&mut self, name: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
     self.remove_property(name)
@@ -995,12 +1252,18 @@ impl Node {
 
   /// Creates a new `Node` as child to the self `Node`
   pub fn add_child(&mut self, child: &mut Node) -> Result<(), String> {
+    if child.is_fragment() {
+      let node_ptr = self.node_ptr_mut()?;
+      return Node::insert_fragment(child, node_ptr, ptr::null_mut())
+        .map_err(|error| error.to_string());
+    }
     child.set_linked();
     unsafe {
       let new_child_ptr = xmlAddChild(self.node_ptr_mut()?, child.node_ptr_mut()?);
       if new_child_ptr.is_null() {
         Err("add_child encountered NULL pointer".to_string())
       } else {
+        child.use_namespace_in_scope();
         Ok(())
       }
     }
@@ -1196,6 +1459,185 @@ impl Node {
     }
   }
 
+  /// internal helper registering `attr`, just set on this node, in the ID
+  /// table of the document if it is an ID. libxml2 registers new IDs itself,
+  /// but keeps the entry of an element unlinked from the tree, which would
+  /// hide this node from `Document::get_element_by_id`. The first attached
+  /// element keeps a duplicated ID, as when parsing.
+  fn sync_id(&self, attr: xmlAttrPtr) {
+    if attr.is_null() {
+      return;
+    }
+    unsafe {
+      let doc = (*attr).doc;
+      if doc.is_null() || xmlIsID(doc, self.node_ptr(), attr) != 1 {
+        return;
+      }
+      let value = xmlNodeListGetString(doc, (*attr).children, 1);
+      if value.is_null() {
+        return;
+      }
+      let current = xmlGetID(doc, value);
+      if current != attr && (current.is_null() || !is_in_document(current as xmlNodePtr, doc)) {
+        if !current.is_null() {
+          xmlRemoveID(doc, current);
+        }
+        xmlAddID(ptr::null_mut(), doc, value, attr);
+      }
+      bindgenFree(value as *mut c_void);
+    }
+  }
+
+  fn is_fragment(&self) -> bool {
+    self.get_type() == Some(NodeType::DocumentFragNode)
+  }
+
+  /// internal helper checking that the children of `fragment` can be moved
+  /// into `parent`, before `insert_fragment` changes anything
+  fn check_fragment_parent(
+    fragment: &Node,
+    parent: xmlNodePtr,
+  ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if parent.is_null() {
+      return Err(From::from(
+        "can not insert a fragment next to a node without parent",
+      ));
+    }
+    match NodeType::from_int(xmlGetNodeType(parent)) {
+      Some(NodeType::ElementNode)
+      | Some(NodeType::DocumentNode)
+      | Some(NodeType::HtmlDocumentNode)
+      | Some(NodeType::DocumentFragNode) => {}
+      _ => {
+        return Err(From::from(
+          "a fragment can only be inserted into an element, a document or a fragment",
+        ));
+      }
+    }
+    let mut ancestor = parent;
+    while !ancestor.is_null() {
+      if ancestor == fragment.node_ptr() {
+        return Err(From::from("can not insert a fragment into itself"));
+      }
+      ancestor = unsafe { (*ancestor).parent };
+    }
+    Ok(())
+  }
+
+  /// internal helper moving the children of `fragment` into `parent` with
+  /// libxml2, before its child `next` or at the end when `next` is null.
+  /// Text children are merged with adjacent text nodes in document order,
+  /// and the wrappers of the children freed by merging are emptied. The
+  /// fragment is left empty.
+  fn insert_fragment(
+    fragment: &mut Node,
+    parent: xmlNodePtr,
+    next: xmlNodePtr,
+  ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    Node::check_fragment_parent(fragment, parent)?;
+    let fragment_ptr = fragment.node_ptr_mut()?;
+    let document = fragment
+      .get_docref()
+      .upgrade()
+      .ok_or("the fragment's document was dropped")?;
+    let mut children = Vec::new();
+    unsafe {
+      let mut child = (*fragment_ptr).children;
+      while !child.is_null() {
+        children.push(child);
+        child = (*child).next;
+      }
+    }
+    if children.is_empty() {
+      return Ok(());
+    }
+
+    let mut inserted = Vec::with_capacity(children.len());
+    unsafe {
+      if next.is_null() {
+        for child in children {
+          xmlUnlinkNode(child);
+          inserted.push((child, xmlAddChild(parent, child)));
+        }
+      } else {
+        // a text child inserted right before a text `next` is merged into
+        // it, ahead of the children still to come: insert them before a
+        // placeholder element instead, which then gives way to `next`
+        let placeholder = xmlNewDocNode(
+          (*parent).doc,
+          ptr::null_mut(),
+          c"fragment".as_ptr() as *const u8,
+          ptr::null(),
+        );
+        if placeholder.is_null() || xmlAddPrevSibling(next, placeholder).is_null() {
+          return Err(From::from("libxml2 could not insert the fragment"));
+        }
+        for child in children {
+          let prev = (*placeholder).prev;
+          let added = if !prev.is_null() && xmlNodeIsText(prev) == 1 && xmlNodeIsText(child) == 1 {
+            // merged into `prev`
+            xmlAddNextSibling(prev, child)
+          } else {
+            xmlAddPrevSibling(placeholder, child)
+          };
+          inserted.push((child, added));
+        }
+        xmlUnlinkNode(placeholder);
+        xmlFreeNode(placeholder);
+        let (last, added) = inserted[inserted.len() - 1];
+        if last == added && xmlNodeIsText(last) == 1 && xmlNodeIsText(next) == 1 {
+          xmlUnlinkNode(last);
+          inserted.push((last, xmlAddPrevSibling(next, last)));
+        }
+      }
+    }
+    let mut failed = false;
+    for (child, added) in inserted {
+      if added.is_null() {
+        failed = true;
+      } else if added != child {
+        // merged into `added`, and freed
+        let wrapper = document.borrow().get_node(child).cloned();
+        document.borrow_mut().forget_node(child);
+        if let Some(wrapper) = wrapper {
+          wrapper.0.borrow_mut().node_ptr = ptr::null_mut();
+        }
+      } else if let Some(wrapper) = document.borrow().get_node(child).cloned() {
+        wrapper.use_namespace_in_scope();
+      }
+      if !added.is_null() && xmlGetNodeType(added) == xmlElementType_XML_ELEMENT_NODE {
+        unsafe { xmlReconciliateNs((*added).doc, added) };
+      }
+    }
+    if failed {
+      Err(From::from("libxml2 could not insert a child of the fragment"))
+    } else {
+      Ok(())
+    }
+  }
+
+  /// internal helper pointing this wrapper to the document a node was moved to
+  pub(crate) fn set_document(&self, document: &DocumentRef) {
+    self.0.borrow_mut().document = Rc::downgrade(document);
+  }
+
+  /// internal helper freeing a node created for its document that was never
+  /// attached to a tree, called by the document before it is freed.
+  ///
+  /// Nodes leave the bookkeeping table when unlinked from a tree, so an
+  /// `Unlinked` node in the table without a parent is one that no tree owns.
+  pub(crate) fn free_if_unattached(&self) {
+    let mut inner = self.0.borrow_mut();
+    let node_ptr = inner.node_ptr;
+    if node_ptr.is_null() || inner.linkage != Linkage::Unlinked {
+      return;
+    }
+    if unsafe { (*node_ptr).parent.is_null() } {
+      unsafe { xmlFreeNode(node_ptr) };
+      inner.node_ptr = ptr::null_mut();
+    }
+  }
+
   /// internal helper to ensure the node is marked as unlinked/removed from the main document tree.
   ///
   /// Transitions from `Linked` to `Unlinked`. Leaves `RustOwned`
@@ -1221,6 +1663,21 @@ impl Node {
     context.findnodes(xpath, Some(self))
   }
 
+  /// Find the descendant elements matching the CSS selector `css`, in
+  /// document order; see the `css` module for the supported syntax.
+  /// Returns a `SelectorError` for a selector that can not be parsed.
+  pub fn select(&self, css: &str) -> Result<Vec<Node>, SelectorError> {
+    let xpath = crate::css::to_xpath_at(css, self.node_ptr())?;
+    self
+      .findnodes(&xpath)
+      .map_err(|_| crate::css::evaluation_error(css))
+  }
+
+  /// Find the first descendant element matching the CSS selector `css`, see `select`
+  pub fn select_first(&self, css: &str) -> Result<Option<Node>, SelectorError> {
+    Ok(self.select(css)?.into_iter().next())
+  }
+
   /// Search this node for XPath `path`, and return only the first match.
   pub fn at_xpath(&self, path: &str, ns_binlings: &[(&str, &str)]) -> Result<Option<Node>, ()> {
     let mut context = Context::from_node(self)?;
@@ -1318,3 +1775,17 @@ fn node_ancestors(node_ptr: xmlNodePtr) -> Vec<xmlNodePtr> {
 }
 
 mod c14n;
+mod reconcile;
+mod traversal;
+
+pub use self::reconcile::ReconcileOptions;
+
+/// The `extended` argument of `xmlCopyNode`: 1 copies recursively, 2 copies
+/// the node with its attributes and namespace declarations only
+fn copy_depth(deep: bool) -> c_int {
+  if deep {
+    1
+  } else {
+    2
+  }
+}
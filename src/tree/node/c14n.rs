@@ -1,11 +1,16 @@
 //! Node canonicalization logic
 //!
 use std::ffi::c_void;
+use std::io::{self, Write};
 
 use crate::{
   bindings::{xmlC14NIsVisibleCallback, xmlElementType, xmlNodePtr},
   c_helpers::xmlGetNodeType,
-  tree::{c14n::*, Node},
+  tree::{
+    c14n::*,
+    document::{canonicalize_into, Visibility},
+    Document, Node,
+  },
 };
 
 use super::node_ancestors;
@@ -21,6 +26,32 @@ impl Node {
 
     document.canonicalize(options, Some((user_data, callback)))
   }
+
+  /// Canonicalize the subtree of this node into `writer`, see `Document::canonicalize_to`
+  pub fn canonicalize_to<W: Write>(
+    &self,
+    mut writer: W,
+    options: CanonicalizationOptions,
+  ) -> io::Result<()> {
+    let doc_ref = self
+      .get_docref()
+      .upgrade()
+      .ok_or_else(|| io::Error::other("the node's document was dropped"))?;
+    let document = Document(doc_ref);
+    canonicalize_into(
+      document.doc_ptr(),
+      options,
+      Visibility::Callback(Some(callback_wrapper), self.node_ptr() as *mut c_void),
+      &mut writer,
+    )
+  }
+
+  /// Canonicalize the subtree of this node and return the resulting bytes
+  pub fn canonicalize_to_bytes(&self, options: CanonicalizationOptions) -> Result<Vec<u8>, ()> {
+    let mut output = Vec::new();
+    self.canonicalize_to(&mut output, options).map_err(|_| ())?;
+    Ok(output)
+  }
 }
 
 unsafe extern "C" fn callback_wrapper(
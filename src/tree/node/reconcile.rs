@@ -0,0 +1,193 @@
+//! Namespace reconciliation of subtrees, and lookup of namespaces in scope
+//!
+use std::error::Error;
+use std::ffi::{c_int, c_void, CString};
+use std::ptr;
+
+use crate::bindings::{
+  xmlDOMWrapFreeCtxt, xmlDOMWrapNewCtxt, xmlDOMWrapReconcileNamespaces, xmlNewNs, xmlNodePtr,
+  xmlNsPtr, xmlReconciliateNs, xmlSearchNs, xmlSearchNsByHref, xmlStrEqual, xmlStrdup,
+};
+use crate::c_helpers::{
+  bindgenFree, xmlAttrNs, xmlGetDoc, xmlGetFirstProperty, xmlNextPropertySibling,
+};
+use crate::readonly::RoNode;
+use crate::tree::nodetype::NodeType;
+use crate::tree::traversal::TraversalEvent;
+use crate::tree::Node;
+
+/// `XML_DOM_RECONNS_REMOVEREDUND` of libxml2's `xmlDOMReconcileNSOptions`
+const REMOVE_REDUNDANT: c_int = 1;
+
+#[derive(Debug, Copy, Clone, Default)]
+/// Options of `Node::reconcile_namespaces_with`
+pub struct ReconcileOptions {
+  /// remove the namespace declarations of the subtree that an ancestor
+  /// already declares with the same prefix and URI
+  pub remove_redundant: bool,
+}
+
+impl Node {
+  /// Fix the namespaces of this subtree after it was moved (`xmlReconciliateNs`):
+  /// namespaces used in the subtree but not declared in scope are declared
+  /// again on this node.
+  pub fn reconcile_namespaces(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let node_ptr = self.node_ptr_mut()?;
+    if unsafe { xmlReconciliateNs(xmlGetDoc(node_ptr), node_ptr) } < 0 {
+      Err(From::from("xmlReconciliateNs failed"))
+    } else {
+      Ok(())
+    }
+  }
+
+  /// Fix the namespaces of this element subtree with libxml2's DOM wrapper
+  /// (`xmlDOMWrapReconcileNamespaces`): declarations shadowed or missing
+  /// in the new scope are added, and the namespace references of elements
+  /// and attributes point to declarations in scope.
+  pub fn reconcile_namespaces_with(
+    &mut self,
+    options: ReconcileOptions,
+  ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if self.get_type() != Some(NodeType::ElementNode) {
+      return Err(From::from("only element subtrees can be reconciled"));
+    }
+    let node_ptr = self.node_ptr_mut()?;
+    let flags = if options.remove_redundant {
+      REMOVE_REDUNDANT
+    } else {
+      0
+    };
+    let status = unsafe {
+      let ctxt = xmlDOMWrapNewCtxt();
+      if ctxt.is_null() {
+        return Err(From::from("libxml2 could not create a DOM wrapper context"));
+      }
+      let status = xmlDOMWrapReconcileNamespaces(ctxt, node_ptr, flags);
+      xmlDOMWrapFreeCtxt(ctxt);
+      status
+    };
+    if status == 0 {
+      Ok(())
+    } else {
+      Err(From::from(format!(
+        "xmlDOMWrapReconcileNamespaces failed with status: {status:?}"
+      )))
+    }
+  }
+}
+
+impl Node {
+  /// internal helper run when a node is attached to a tree: a node created
+  /// by `Node::new_ns` uses a declaration of its namespace found in scope
+  /// instead of its own, see there
+  pub(super) fn use_namespace_in_scope(&self) {
+    let node_ptr = {
+      let mut inner = self.0.borrow_mut();
+      if !std::mem::take(&mut inner.ns_on_attach) {
+        return;
+      }
+      inner.node_ptr
+    };
+    unsafe {
+      let own = (*node_ptr).ns;
+      let parent = (*node_ptr).parent;
+      if own.is_null() || parent.is_null() {
+        return;
+      }
+      // attributes have no default namespace
+      let found = if (*own).prefix.is_null() || !used_by_attributes(node_ptr, own) {
+        xmlSearchNsByHref((*node_ptr).doc, parent, (*own).href)
+      } else {
+        prefixed_ns_in_scope(parent, (*own).href)
+      };
+      if found.is_null() {
+        return;
+      }
+      if xmlStrEqual((*own).prefix, (*found).prefix) == 0 {
+        // rename the own declaration after the one in scope, unless the
+        // node already declares that prefix
+        let mut declared = (*node_ptr).nsDef;
+        while !declared.is_null() {
+          if declared != own && xmlStrEqual((*declared).prefix, (*found).prefix) == 1 {
+            return;
+          }
+          declared = (*declared).next;
+        }
+        let old_prefix = (*own).prefix;
+        (*own).prefix = if (*found).prefix.is_null() {
+          ptr::null()
+        } else {
+          xmlStrdup((*found).prefix)
+        };
+        if !old_prefix.is_null() {
+          bindgenFree(old_prefix as *mut c_void);
+        }
+      }
+      // the own declaration is now redundant
+      let ctxt = xmlDOMWrapNewCtxt();
+      if !ctxt.is_null() {
+        xmlDOMWrapReconcileNamespaces(ctxt, node_ptr, REMOVE_REDUNDANT);
+        xmlDOMWrapFreeCtxt(ctxt);
+      }
+    }
+  }
+}
+
+/// Whether attributes of the subtree of `node` are in the namespace `ns`
+fn used_by_attributes(node: xmlNodePtr, ns: xmlNsPtr) -> bool {
+  RoNode(node).traverse().any(|event| match event {
+    TraversalEvent::Enter(element) if element.is_element_node() => {
+      let mut attr = xmlGetFirstProperty(element.0);
+      while !attr.is_null() {
+        if xmlAttrNs(attr) == ns {
+          return true;
+        }
+        attr = xmlNextPropertySibling(attr);
+      }
+      false
+    }
+    _ => false,
+  })
+}
+
+/// A declaration of `href` with a prefix, in scope at `node` and not
+/// shadowed there
+pub(super) unsafe fn prefixed_ns_in_scope(node: xmlNodePtr, href: *const u8) -> xmlNsPtr {
+  unsafe {
+    let doc = (*node).doc;
+    let found = xmlSearchNsByHref(doc, node, href);
+    if found.is_null() || !(*found).prefix.is_null() {
+      return found;
+    }
+    // the nearest declaration is the default namespace, look for another one
+    let mut element = node;
+    while !element.is_null() {
+      let mut ns = (*element).nsDef;
+      while !ns.is_null() {
+        if !(*ns).prefix.is_null()
+          && xmlStrEqual((*ns).href, href) == 1
+          && xmlSearchNs(doc, node, (*ns).prefix) == ns
+        {
+          return ns;
+        }
+        ns = (*ns).next;
+      }
+      element = (*element).parent;
+    }
+    ptr::null_mut()
+  }
+}
+
+/// Declare `href` on `node` with a prefix `ns0`, `ns1`, ... not in scope there
+pub(super) unsafe fn declare_generated_prefix(node: xmlNodePtr, href: *const u8) -> xmlNsPtr {
+  unsafe {
+    let doc = (*node).doc;
+    for index in 0.. {
+      let prefix = CString::new(format!("ns{index}")).unwrap();
+      if xmlSearchNs(doc, node, prefix.as_bytes().as_ptr()).is_null() {
+        return xmlNewNs(node, href, prefix.as_bytes().as_ptr());
+      }
+    }
+    unreachable!("ran out of namespace prefixes")
+  }
+}
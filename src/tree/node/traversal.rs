@@ -0,0 +1,178 @@
+//! Node traversal iterators and tree walker
+//!
+use std::error::Error;
+
+use crate::bindings::{xmlNodePtr, xmlReplaceNode};
+use crate::readonly::RoNode;
+use crate::tree::traversal::{first_child, TraversalEvent, WalkAction};
+use crate::tree::{DocumentRef, Node};
+
+impl Node {
+  /// Iterates over the child nodes. The next child is looked up before a
+  /// child is returned, so the returned child may be unlinked meanwhile.
+  pub fn children(&self) -> impl Iterator<Item = Node> {
+    let document = self.document_ref();
+    self.as_ronode().children().map(move |child| wrap(&document, child))
+  }
+
+  /// Iterates over the child elements, see `children`
+  pub fn element_children(&self) -> impl Iterator<Item = Node> {
+    let document = self.document_ref();
+    self
+      .as_ronode()
+      .element_children()
+      .map(move |child| wrap(&document, child))
+  }
+
+  /// Iterates over the descendants in document order, with their depth
+  /// below this node (1 for children). Do not modify the tree meanwhile.
+  pub fn descendants(&self) -> impl Iterator<Item = (Node, usize)> {
+    let document = self.document_ref();
+    self
+      .as_ronode()
+      .descendants()
+      .map(move |(node, depth)| (wrap(&document, node), depth))
+  }
+
+  /// Iterates over the following siblings, nearest first, see `children`
+  pub fn following_siblings(&self) -> impl Iterator<Item = Node> {
+    let document = self.document_ref();
+    self
+      .as_ronode()
+      .following_siblings()
+      .map(move |sibling| wrap(&document, sibling))
+  }
+
+  /// Iterates over the preceding siblings, nearest first, see `children`
+  pub fn preceding_siblings(&self) -> impl Iterator<Item = Node> {
+    let document = self.document_ref();
+    self
+      .as_ronode()
+      .preceding_siblings()
+      .map(move |sibling| wrap(&document, sibling))
+  }
+
+  /// Iterates over the ancestors, from the parent up to the document node.
+  /// Unlike `ancestors`, this does not collect them first.
+  pub fn ancestors_iter(&self) -> impl Iterator<Item = Node> {
+    let document = self.document_ref();
+    self
+      .as_ronode()
+      .ancestors_iter()
+      .map(move |ancestor| wrap(&document, ancestor))
+  }
+
+  /// Depth-first traversal of the subtree of this node, entering and
+  /// leaving every node, this one included. Do not modify the tree
+  /// meanwhile.
+  pub fn traverse(&self) -> impl Iterator<Item = TraversalEvent<Node>> {
+    let document = self.document_ref();
+    self.as_ronode().traverse().map(move |event| match event {
+      TraversalEvent::Enter(node) => TraversalEvent::Enter(wrap(&document, node)),
+      TraversalEvent::Leave(node) => TraversalEvent::Leave(wrap(&document, node)),
+    })
+  }
+
+  /// Visit the descendants of this node in document order with their depth
+  /// below it (1 for children), letting `visitor` decide after each node
+  /// whether to skip its children, remove or replace it, or stop.
+  ///
+  /// The visitor may modify the visited node and its subtree, e.g. add
+  /// children that are then visited, but should leave the rest of the tree
+  /// alone. The next node is looked up after the visitor returns.
+  pub fn walk<F>(&self, mut visitor: F) -> Result<(), Box<dyn Error + Send + Sync>>
+  where
+    F: FnMut(&mut Node, usize) -> WalkAction,
+  {
+    let document = self.document_ref();
+    let root = self.node_ptr();
+    let mut next = first_child(self.as_ronode()).map(|child| (child, 1));
+    while let Some((current, depth)) = next {
+      let mut node = wrap(&document, current);
+      next = match visitor(&mut node, depth) {
+        WalkAction::Continue => match first_child(current) {
+          Some(child) => Some((child, depth + 1)),
+          None => following(root, current, depth),
+        },
+        WalkAction::SkipChildren => following(root, current, depth),
+        WalkAction::Remove => {
+          let next = following(root, current, depth);
+          node.unlink_node();
+          next
+        }
+        WalkAction::Replace(mut replacement) if replacement.is_fragment() => {
+          let next = following(root, current, depth);
+          node.replace_with_fragment(&mut replacement)?;
+          next
+        }
+        WalkAction::Replace(replacement) => {
+          node.replace_with(&replacement)?;
+          following(root, replacement.as_ronode(), depth)
+        }
+        WalkAction::Stop => None,
+      };
+    }
+    Ok(())
+  }
+
+  /// Put `replacement` in the place of this node, which gets unlinked
+  fn replace_with(&mut self, replacement: &Node) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if replacement == self {
+      return Ok(());
+    }
+    if self
+      .as_ronode()
+      .ancestors_iter()
+      .any(|ancestor| ancestor.node_ptr() == replacement.node_ptr())
+    {
+      return Err(From::from("can not replace a node with one of its ancestors"));
+    }
+    if unsafe { xmlReplaceNode(self.node_ptr(), replacement.node_ptr()) }.is_null() {
+      return Err(From::from("xmlReplaceNode returned NULL"));
+    }
+    self.set_unlinked();
+    replacement.set_linked();
+    Ok(())
+  }
+
+  /// Move the children of `fragment` in the place of this node, which gets unlinked
+  fn replace_with_fragment(
+    &mut self,
+    fragment: &mut Node,
+  ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let node_ptr = self.node_ptr();
+    let (parent, next) = unsafe { ((*node_ptr).parent, (*node_ptr).next) };
+    Node::check_fragment_parent(fragment, parent)?;
+    self.unlink_node();
+    Node::insert_fragment(fragment, parent, next)
+  }
+
+  fn as_ronode(&self) -> RoNode {
+    RoNode(self.node_ptr())
+  }
+
+  fn document_ref(&self) -> DocumentRef {
+    self.get_docref().upgrade().unwrap()
+  }
+}
+
+fn wrap(document: &DocumentRef, node: RoNode) -> Node {
+  Node::wrap(node.node_ptr(), document)
+}
+
+/// The node after the subtree of `node` in a walk of the subtree of `root`, with its depth
+fn following(root: xmlNodePtr, node: RoNode, depth: usize) -> Option<(RoNode, usize)> {
+  let mut current = node;
+  let mut depth = depth;
+  loop {
+    if let Some(sibling) = current.get_next_sibling() {
+      return Some((sibling, depth));
+    }
+    let parent = current.get_parent()?;
+    if parent.node_ptr() == root {
+      return None;
+    }
+    current = parent;
+    depth -= 1;
+  }
+}
@@ -0,0 +1,231 @@
+//! Lazy tree traversal iterators
+//!
+//! The iterators walk the libxml2 tree links directly and hold no more than
+//! a node or two, so iterating over `RoNode`s does not allocate. The `Node`
+//! methods of the same names wrap these iterators.
+//!
+//! Iterating over children or siblings prefetches the next node before
+//! yielding the current one, so the yielded node may be unlinked or moved
+//! meanwhile. `descendants` and `traverse` follow the links of the node
+//! they last yielded: do not modify the tree while using them, see
+//! `Node::walk` for that.
+
+use crate::c_helpers::{xmlGetFirstChild, xmlGetNodeType, xmlGetParent, xmlNextSibling, xmlPrevSibling};
+use crate::readonly::RoNode;
+use crate::tree::nodetype::NodeType;
+use crate::tree::Node;
+
+/// Event of a depth-first traversal, see `RoNode::traverse`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TraversalEvent<N> {
+  /// the traversal reaches the node, before its children
+  Enter(N),
+  /// the traversal leaves the node, after its children
+  Leave(N),
+}
+
+impl<N> TraversalEvent<N> {
+  /// The node entered or left
+  pub fn node(&self) -> &N {
+    match self {
+      TraversalEvent::Enter(node) | TraversalEvent::Leave(node) => node,
+    }
+  }
+}
+
+/// What `Node::walk` does after visiting a node
+#[derive(Debug, Clone)]
+pub enum WalkAction {
+  /// visit the children of the node, then go on with the rest of the tree
+  Continue,
+  /// go on with the rest of the tree, without visiting the children
+  SkipChildren,
+  /// unlink the node, as `Node::unlink_node` does, and go on after it
+  Remove,
+  /// put the given node in place of the visited node, which gets unlinked,
+  /// and go on after it; the replacement is not visited, and is moved if it
+  /// is part of a tree. A document fragment is replaced by its children,
+  /// which are moved as by `Node::add_child` and not visited either.
+  Replace(Node),
+  /// end the walk
+  Stop,
+}
+
+fn as_option(node_ptr: crate::bindings::xmlNodePtr) -> Option<RoNode> {
+  (!node_ptr.is_null()).then_some(RoNode(node_ptr))
+}
+
+/// Children of a node in document order, see `RoNode::children`
+#[derive(Debug, Clone)]
+pub struct Children {
+  pub(crate) next: Option<RoNode>,
+}
+
+impl Iterator for Children {
+  type Item = RoNode;
+
+  fn next(&mut self) -> Option<RoNode> {
+    let current = self.next?;
+    self.next = as_option(xmlNextSibling(current.0));
+    Some(current)
+  }
+}
+
+/// Element children of a node in document order, see `RoNode::element_children`
+#[derive(Debug, Clone)]
+pub struct ElementChildren {
+  pub(crate) children: Children,
+}
+
+impl Iterator for ElementChildren {
+  type Item = RoNode;
+
+  fn next(&mut self) -> Option<RoNode> {
+    self.children.by_ref().find(|child| child.is_element_node())
+  }
+}
+
+/// Following or preceding siblings of a node, nearest first,
+/// see `RoNode::following_siblings` and `RoNode::preceding_siblings`
+#[derive(Debug, Clone)]
+pub struct Siblings {
+  pub(crate) next: Option<RoNode>,
+  pub(crate) following: bool,
+}
+
+impl Iterator for Siblings {
+  type Item = RoNode;
+
+  fn next(&mut self) -> Option<RoNode> {
+    let current = self.next?;
+    self.next = as_option(if self.following {
+      xmlNextSibling(current.0)
+    } else {
+      xmlPrevSibling(current.0)
+    });
+    Some(current)
+  }
+}
+
+/// Ancestors of a node, nearest first and up to the document node,
+/// see `RoNode::ancestors_iter`
+#[derive(Debug, Clone)]
+pub struct Ancestors {
+  pub(crate) next: Option<RoNode>,
+}
+
+impl Iterator for Ancestors {
+  type Item = RoNode;
+
+  fn next(&mut self) -> Option<RoNode> {
+    let current = self.next?;
+    self.next = as_option(xmlGetParent(current.0));
+    Some(current)
+  }
+}
+
+/// Depth-first traversal of a subtree, see `RoNode::traverse`
+#[derive(Debug, Clone)]
+pub struct Traverse {
+  root: RoNode,
+  next: Option<TraversalEvent<RoNode>>,
+  /// the node of the last event, if it entered the node
+  entered: Option<RoNode>,
+}
+
+impl Traverse {
+  pub(crate) fn new(root: RoNode) -> Self {
+    Traverse {
+      root,
+      next: Some(TraversalEvent::Enter(root)),
+      entered: None,
+    }
+  }
+
+  /// Do not enter the children of the node that was just entered:
+  /// the next event leaves it. Does nothing if the last event left a node.
+  pub fn skip_children(&mut self) {
+    if let Some(node) = self.entered.take()
+      && let Some(TraversalEvent::Enter(_)) = self.next
+    {
+      self.next = Some(TraversalEvent::Leave(node));
+    }
+  }
+
+  fn after(&self, event: TraversalEvent<RoNode>) -> Option<TraversalEvent<RoNode>> {
+    match event {
+      TraversalEvent::Enter(node) => Some(match first_child(node) {
+        Some(child) => TraversalEvent::Enter(child),
+        None => TraversalEvent::Leave(node),
+      }),
+      TraversalEvent::Leave(node) if node == self.root => None,
+      TraversalEvent::Leave(node) => match as_option(xmlNextSibling(node.0)) {
+        Some(sibling) => Some(TraversalEvent::Enter(sibling)),
+        None => as_option(xmlGetParent(node.0)).map(TraversalEvent::Leave),
+      },
+    }
+  }
+}
+
+impl Iterator for Traverse {
+  type Item = TraversalEvent<RoNode>;
+
+  fn next(&mut self) -> Option<TraversalEvent<RoNode>> {
+    let event = self.next?;
+    self.next = self.after(event);
+    self.entered = match event {
+      TraversalEvent::Enter(node) => Some(node),
+      TraversalEvent::Leave(_) => None,
+    };
+    Some(event)
+  }
+}
+
+/// The first child of `node` within its subtree: the children of an entity
+/// reference belong to the entity declaration, so they are not descended into
+pub(crate) fn first_child(node: RoNode) -> Option<RoNode> {
+  if NodeType::from_int(xmlGetNodeType(node.0)) == Some(NodeType::EntityRefNode) {
+    None
+  } else {
+    as_option(xmlGetFirstChild(node.0))
+  }
+}
+
+/// Descendants of a node in document order with their depth below it
+/// (1 for children), see `RoNode::descendants`
+#[derive(Debug, Clone)]
+pub struct Descendants {
+  traverse: Traverse,
+  depth: usize,
+}
+
+impl Descendants {
+  pub(crate) fn new(root: RoNode) -> Self {
+    let mut traverse = Traverse::new(root);
+    // the root itself is not a descendant
+    traverse.next();
+    traverse.entered = None;
+    Descendants { traverse, depth: 0 }
+  }
+
+  /// Do not descend into the node that was just returned
+  pub fn skip_children(&mut self) {
+    self.traverse.skip_children();
+  }
+}
+
+impl Iterator for Descendants {
+  type Item = (RoNode, usize);
+
+  fn next(&mut self) -> Option<(RoNode, usize)> {
+    loop {
+      match self.traverse.next()? {
+        TraversalEvent::Enter(node) => {
+          self.depth += 1;
+          return Some((node, self.depth));
+        }
+        TraversalEvent::Leave(_) => self.depth = self.depth.saturating_sub(1),
+      }
+    }
+  }
+}
@@ -0,0 +1,579 @@
+//! XML Signature (XML-DSig) helpers
+//!
+//! Parses `ds:Signature` elements, computes the digests of their references
+//! (enveloped-signature and canonicalization transforms, SHA-1/256/512) and
+//! canonicalizes their `ds:SignedInfo`. The signature itself is computed
+//! and checked by a caller-supplied `Signer` / `SignatureVerifier`, so any
+//! crypto library can be plugged in.
+//!
+//! Only same-document references are supported: `URI=""` for the whole
+//! document and `URI="#id"` for the element whose `ID`, `Id`, `id` or
+//! `xml:id` attribute is `id`. An id carried by several elements is
+//! rejected, as a guard against signature wrapping. A reference without
+//! `URI` is only resolved by the caller, see `Reference::compute_digest_of`.
+//! After a successful `Signature::verify`, only trust the nodes returned by
+//! `Reference::resolve`.
+//!
+//! The transforms of a reference are an optional enveloped-signature
+//! transform followed by an optional canonicalization; other chains are
+//! rejected.
+//!
+//! ```no_run
+//! use libxml::parser::Parser;
+//! use libxml::xmldsig::{self, SignatureVerifier};
+//!
+//! /// Checks RSA-SHA256 signatures with the public key of the signer
+//! struct RsaVerifier {
+//!   public_key_der: Vec<u8>,
+//! }
+//!
+//! impl SignatureVerifier for RsaVerifier {
+//!   fn verify(&self, algorithm: &str, data: &[u8], signature: &[u8]) -> bool {
+//!     algorithm == "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256"
+//!       && rsa_sha256_verify(&self.public_key_der, data, signature)
+//!   }
+//! }
+//! # /// PKCS#1 v1.5 verification, e.g. with the `rsa` or `openssl` crate
+//! # fn rsa_sha256_verify(_key: &[u8], _data: &[u8], _signature: &[u8]) -> bool {
+//! #   false
+//! # }
+//!
+//! let verifier = RsaVerifier {
+//!   public_key_der: std::fs::read("signer_key.der").unwrap(),
+//! };
+//! let doc = Parser::default().parse_file("signed.xml").unwrap();
+//! for node in xmldsig::find_signatures(&doc) {
+//!   let signature = xmldsig::Signature::parse(node).unwrap();
+//!   signature.verify(&doc, &verifier).unwrap();
+//! }
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha2::digest::DynDigest;
+
+use crate::readonly::RoNode;
+use crate::tree::c14n::{CanonicalizationFilter, CanonicalizationMode, CanonicalizationOptions};
+use crate::tree::{Document, NodeType};
+
+/// Namespace of the XML-DSig elements
+pub const DSIG_NAMESPACE: &str = "http://www.w3.org/2000/09/xmldsig#";
+/// Namespace of the `InclusiveNamespaces` element of exclusive canonicalization
+pub const EXC_C14N_NAMESPACE: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+/// Algorithm URI of the enveloped-signature transform
+pub const ENVELOPED_SIGNATURE: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Errors of signature parsing, verification and signing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XmlDsigError {
+  /// a required `ds:` element or attribute is missing
+  Missing(&'static str),
+  /// an algorithm URI that this module does not implement
+  UnsupportedAlgorithm(String),
+  /// a `DigestValue` or `SignatureValue` is not valid base64
+  InvalidBase64(&'static str),
+  /// a reference URI that is external or does not match any element
+  UnresolvedReference(String),
+  /// a reference id carried by more than one element
+  AmbiguousReference(String),
+  /// reference transforms that can not be applied in their order
+  UnsupportedTransforms,
+  /// libxml2 could not canonicalize the referenced data
+  Canonicalization,
+  /// the digest of the reference with this URI does not match its `DigestValue`
+  DigestMismatch(String),
+  /// the `SignatureValue` does not match the canonical `SignedInfo`
+  InvalidSignature,
+  /// the `Signer` failed to sign the canonical `SignedInfo`
+  SigningFailed,
+}
+
+impl fmt::Display for XmlDsigError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      XmlDsigError::Missing(what) => write!(f, "missing {what}"),
+      XmlDsigError::UnsupportedAlgorithm(uri) => write!(f, "unsupported algorithm {uri}"),
+      XmlDsigError::InvalidBase64(what) => write!(f, "{what} is not valid base64"),
+      XmlDsigError::UnresolvedReference(uri) => write!(f, "could not resolve reference \"{uri}\""),
+      XmlDsigError::AmbiguousReference(uri) => {
+        write!(f, "reference \"{uri}\" matches more than one element")
+      }
+      XmlDsigError::UnsupportedTransforms => write!(f, "unsupported chain of transforms"),
+      XmlDsigError::Canonicalization => write!(f, "canonicalization failed"),
+      XmlDsigError::DigestMismatch(uri) => write!(f, "digest mismatch for reference \"{uri}\""),
+      XmlDsigError::InvalidSignature => write!(f, "invalid signature value"),
+      XmlDsigError::SigningFailed => write!(f, "signing failed"),
+    }
+  }
+}
+
+impl Error for XmlDsigError {}
+
+/// Checks a signature value, e.g. with an RSA public key or an HMAC secret
+pub trait SignatureVerifier {
+  /// Whether `signature` is valid for `data` under the `SignatureMethod`
+  /// `algorithm` URI. Return `false` for algorithms that are not supported.
+  fn verify(&self, algorithm: &str, data: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Computes a signature value, e.g. with an RSA private key or an HMAC secret
+pub trait Signer {
+  /// Sign `data` under the `SignatureMethod` `algorithm` URI
+  fn sign(&self, algorithm: &str, data: &[u8]) -> Result<Vec<u8>, ()>;
+}
+
+/// Digest algorithm of a reference
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DigestAlgorithm {
+  /// `http://www.w3.org/2000/09/xmldsig#sha1`
+  Sha1,
+  /// `http://www.w3.org/2001/04/xmlenc#sha256`
+  Sha256,
+  /// `http://www.w3.org/2001/04/xmlenc#sha512`
+  Sha512,
+}
+
+impl DigestAlgorithm {
+  /// The algorithm identified by a `DigestMethod` URI
+  pub fn from_uri(uri: &str) -> Option<Self> {
+    match uri {
+      "http://www.w3.org/2000/09/xmldsig#sha1" => Some(DigestAlgorithm::Sha1),
+      "http://www.w3.org/2001/04/xmlenc#sha256" => Some(DigestAlgorithm::Sha256),
+      "http://www.w3.org/2001/04/xmlenc#sha512" => Some(DigestAlgorithm::Sha512),
+      _ => None,
+    }
+  }
+
+  /// The `DigestMethod` URI of the algorithm
+  pub fn uri(self) -> &'static str {
+    match self {
+      DigestAlgorithm::Sha1 => "http://www.w3.org/2000/09/xmldsig#sha1",
+      DigestAlgorithm::Sha256 => "http://www.w3.org/2001/04/xmlenc#sha256",
+      DigestAlgorithm::Sha512 => "http://www.w3.org/2001/04/xmlenc#sha512",
+    }
+  }
+
+  /// The digest of `data`
+  pub fn digest(self, data: &[u8]) -> Vec<u8> {
+    let mut hasher = self.hasher();
+    hasher.update(data);
+    hasher.finalize().into_vec()
+  }
+
+  fn hasher(self) -> Box<dyn DynDigest> {
+    match self {
+      DigestAlgorithm::Sha1 => Box::new(sha1::Sha1::default()),
+      DigestAlgorithm::Sha256 => Box::new(sha2::Sha256::default()),
+      DigestAlgorithm::Sha512 => Box::new(sha2::Sha512::default()),
+    }
+  }
+}
+
+/// Feeds the canonical form straight into a hasher
+struct DigestWriter(Box<dyn DynDigest>);
+
+impl Write for DigestWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.0.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+/// The canonicalization options for a `CanonicalizationMethod` or `Transform` URI
+pub fn canonicalization_from_uri(uri: &str) -> Option<CanonicalizationOptions> {
+  let (mode, with_comments) = match uri {
+    "http://www.w3.org/TR/2001/REC-xml-c14n-20010315" => (CanonicalizationMode::Canonical1_0, false),
+    "http://www.w3.org/TR/2001/REC-xml-c14n-20010315#WithComments" => {
+      (CanonicalizationMode::Canonical1_0, true)
+    }
+    "http://www.w3.org/2006/12/xml-c14n11" => (CanonicalizationMode::Canonical1_1, false),
+    "http://www.w3.org/2006/12/xml-c14n11#WithComments" => (CanonicalizationMode::Canonical1_1, true),
+    "http://www.w3.org/2001/10/xml-exc-c14n#" => (CanonicalizationMode::ExclusiveCanonical1_0, false),
+    "http://www.w3.org/2001/10/xml-exc-c14n#WithComments" => {
+      (CanonicalizationMode::ExclusiveCanonical1_0, true)
+    }
+    _ => return None,
+  };
+  Some(CanonicalizationOptions {
+    mode,
+    with_comments,
+    inclusive_ns_prefixes: Vec::new(),
+  })
+}
+
+/// A reference transform
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transform {
+  /// Removes the `ds:Signature` holding the reference from the referenced data
+  EnvelopedSignature,
+  /// Canonicalizes the referenced data, with the `InclusiveNamespaces`
+  /// `PrefixList` of exclusive canonicalization as `inclusive_ns_prefixes`
+  Canonicalization(CanonicalizationOptions),
+}
+
+impl Transform {
+  fn parse(node: RoNode) -> Result<Self, XmlDsigError> {
+    let algorithm = algorithm_of(node)?;
+    if algorithm == ENVELOPED_SIGNATURE {
+      return Ok(Transform::EnvelopedSignature);
+    }
+    let mut options = canonicalization_from_uri(&algorithm)
+      .ok_or(XmlDsigError::UnsupportedAlgorithm(algorithm))?;
+    let prefixes = child_element(node, EXC_C14N_NAMESPACE, "InclusiveNamespaces")
+      .filter(|_| options.mode == CanonicalizationMode::ExclusiveCanonical1_0)
+      .and_then(|inclusive| inclusive.get_attribute_no_ns("PrefixList"));
+    if let Some(prefixes) = prefixes {
+      options.inclusive_ns_prefixes = prefixes.split_whitespace().map(String::from).collect();
+    }
+    Ok(Transform::Canonicalization(options))
+  }
+}
+
+/// A `ds:Reference`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+  /// the `URI` attribute, `None` when absent
+  pub uri: Option<String>,
+  /// the transforms, in document order
+  pub transforms: Vec<Transform>,
+  /// the `DigestMethod`
+  pub digest_method: DigestAlgorithm,
+  /// the decoded `DigestValue`
+  pub digest_value: Vec<u8>,
+}
+
+impl Reference {
+  fn parse(node: RoNode) -> Result<Self, XmlDsigError> {
+    let transforms = match child_element(node, DSIG_NAMESPACE, "Transforms") {
+      Some(transforms) => child_elements(transforms, DSIG_NAMESPACE, "Transform")
+        .map(Transform::parse)
+        .collect::<Result<_, _>>()?,
+      None => Vec::new(),
+    };
+    let digest_method = child_element(node, DSIG_NAMESPACE, "DigestMethod")
+      .ok_or(XmlDsigError::Missing("DigestMethod"))?;
+    let algorithm = algorithm_of(digest_method)?;
+    let digest_method =
+      DigestAlgorithm::from_uri(&algorithm).ok_or(XmlDsigError::UnsupportedAlgorithm(algorithm))?;
+    let digest_value = child_element(node, DSIG_NAMESPACE, "DigestValue")
+      .ok_or(XmlDsigError::Missing("DigestValue"))?;
+    let reference = Reference {
+      uri: node.get_attribute_no_ns("URI"),
+      transforms,
+      digest_method,
+      digest_value: decode_base64(digest_value, "DigestValue")?,
+    };
+    reference.transform_chain()?;
+    Ok(reference)
+  }
+
+  /// The node referenced in `doc`: the document node for `URI=""`, the
+  /// element carrying the id for `URI="#id"`. A reference without `URI`
+  /// is `Missing("URI")`.
+  pub fn resolve(&self, doc: &Document) -> Result<RoNode, XmlDsigError> {
+    let uri = self.uri.as_deref().ok_or(XmlDsigError::Missing("URI"))?;
+    let unresolved = || XmlDsigError::UnresolvedReference(uri.to_owned());
+    let root = doc.get_root_readonly().ok_or_else(unresolved)?;
+    if uri.is_empty() {
+      return root.get_parent().ok_or_else(unresolved);
+    }
+    let id = uri.strip_prefix('#').ok_or_else(unresolved)?;
+    let mut matches = Vec::new();
+    find_elements(root, &mut |element| has_id(element, id), &mut matches);
+    match matches.as_slice() {
+      [element] => Ok(*element),
+      [] => Err(unresolved()),
+      _ => Err(XmlDsigError::AmbiguousReference(uri.to_owned())),
+    }
+  }
+
+  /// Compute the digest of the referenced data after the transforms.
+  /// `signature` is the `ds:Signature` element holding the reference,
+  /// required by the enveloped-signature transform.
+  ///
+  /// Without a canonicalization transform, the data is canonicalized with
+  /// inclusive C14N 1.0. Comments are always removed, as XML-DSig requires
+  /// for same-document references.
+  pub fn compute_digest(
+    &self,
+    doc: &Document,
+    signature: Option<RoNode>,
+  ) -> Result<Vec<u8>, XmlDsigError> {
+    self.compute_digest_of(doc, self.resolve(doc)?, signature)
+  }
+
+  /// Compute the digest of `referent` after the transforms, see
+  /// `compute_digest`. For references the caller resolves, such as those
+  /// without `URI`.
+  pub fn compute_digest_of(
+    &self,
+    doc: &Document,
+    referent: RoNode,
+    signature: Option<RoNode>,
+  ) -> Result<Vec<u8>, XmlDsigError> {
+    let (enveloped, canonicalization) = self.transform_chain()?;
+    let excluded = if enveloped {
+      Some(signature.ok_or(XmlDsigError::Missing("Signature"))?)
+    } else {
+      None
+    };
+    let mut options = canonicalization.cloned().unwrap_or(CanonicalizationOptions {
+      mode: CanonicalizationMode::Canonical1_0,
+      ..CanonicalizationOptions::default()
+    });
+    options.with_comments = false;
+
+    let visible = |node: RoNode, _parent: Option<RoNode>| {
+      is_ancestor_or_self(referent, node) && !excluded.is_some_and(|excluded| is_ancestor_or_self(excluded, node))
+    };
+    let mut writer = DigestWriter(self.digest_method.hasher());
+    doc
+      .canonicalize_filtered_to(&mut writer, options, CanonicalizationFilter::Visible(&visible))
+      .map_err(|_| XmlDsigError::Canonicalization)?;
+    Ok(writer.0.finalize().into_vec())
+  }
+
+  /// Whether the transforms start with the enveloped-signature transform,
+  /// and their final canonicalization: the chains applied in order
+  fn transform_chain(&self) -> Result<(bool, Option<&CanonicalizationOptions>), XmlDsigError> {
+    match self.transforms.as_slice() {
+      [] => Ok((false, None)),
+      [Transform::EnvelopedSignature] => Ok((true, None)),
+      [Transform::Canonicalization(options)] => Ok((false, Some(options))),
+      [Transform::EnvelopedSignature, Transform::Canonicalization(options)] => {
+        Ok((true, Some(options)))
+      }
+      _ => Err(XmlDsigError::UnsupportedTransforms),
+    }
+  }
+}
+
+/// A `ds:SignedInfo`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedInfo {
+  /// the `CanonicalizationMethod` of the `SignedInfo` itself
+  pub canonicalization: CanonicalizationOptions,
+  /// the `SignatureMethod` algorithm URI, passed to `Signer` and `SignatureVerifier`
+  pub signature_method: String,
+  /// the references, in document order
+  pub references: Vec<Reference>,
+}
+
+/// A parsed `ds:Signature` element
+#[derive(Debug, Clone)]
+pub struct Signature {
+  /// the `SignedInfo`
+  pub signed_info: SignedInfo,
+  /// the decoded `SignatureValue`
+  pub signature_value: Vec<u8>,
+  node: RoNode,
+  signed_info_node: RoNode,
+  signature_value_node: RoNode,
+  digest_value_nodes: Vec<RoNode>,
+}
+
+impl Signature {
+  /// Parse the `ds:Signature` element `node`.
+  /// Empty `DigestValue` and `SignatureValue` elements are accepted, for templates to `sign`.
+  pub fn parse(node: RoNode) -> Result<Self, XmlDsigError> {
+    if !is_dsig_element(node, "Signature") {
+      return Err(XmlDsigError::Missing("Signature"));
+    }
+    let signed_info_node =
+      child_element(node, DSIG_NAMESPACE, "SignedInfo").ok_or(XmlDsigError::Missing("SignedInfo"))?;
+    let canonicalization = child_element(signed_info_node, DSIG_NAMESPACE, "CanonicalizationMethod")
+      .ok_or(XmlDsigError::Missing("CanonicalizationMethod"))?;
+    let canonicalization = match Transform::parse(canonicalization)? {
+      Transform::Canonicalization(options) => options,
+      Transform::EnvelopedSignature => {
+        return Err(XmlDsigError::UnsupportedAlgorithm(ENVELOPED_SIGNATURE.to_owned()))
+      }
+    };
+    let signature_method = child_element(signed_info_node, DSIG_NAMESPACE, "SignatureMethod")
+      .ok_or(XmlDsigError::Missing("SignatureMethod"))
+      .and_then(algorithm_of)?;
+    let reference_nodes: Vec<RoNode> =
+      child_elements(signed_info_node, DSIG_NAMESPACE, "Reference").collect();
+    if reference_nodes.is_empty() {
+      return Err(XmlDsigError::Missing("Reference"));
+    }
+    let references = reference_nodes
+      .iter()
+      .map(|reference| Reference::parse(*reference))
+      .collect::<Result<_, _>>()?;
+    let digest_value_nodes = reference_nodes
+      .iter()
+      .filter_map(|reference| child_element(*reference, DSIG_NAMESPACE, "DigestValue"))
+      .collect();
+    let signature_value_node = child_element(node, DSIG_NAMESPACE, "SignatureValue")
+      .ok_or(XmlDsigError::Missing("SignatureValue"))?;
+
+    Ok(Signature {
+      signed_info: SignedInfo {
+        canonicalization,
+        signature_method,
+        references,
+      },
+      signature_value: decode_base64(signature_value_node, "SignatureValue")?,
+      node,
+      signed_info_node,
+      signature_value_node,
+      digest_value_nodes,
+    })
+  }
+
+  /// The `ds:Signature` element
+  pub fn node(&self) -> RoNode {
+    self.node
+  }
+
+  /// The canonical form of the `SignedInfo` element, the data that is signed
+  pub fn canonicalize_signed_info(&self, doc: &Document) -> Result<Vec<u8>, XmlDsigError> {
+    let signed_info = self.signed_info_node;
+    let visible = |node: RoNode, _parent: Option<RoNode>| is_ancestor_or_self(signed_info, node);
+    let mut output = Vec::new();
+    doc
+      .canonicalize_filtered_to(
+        &mut output,
+        self.signed_info.canonicalization.clone(),
+        CanonicalizationFilter::Visible(&visible),
+      )
+      .map_err(|_| XmlDsigError::Canonicalization)?;
+    Ok(output)
+  }
+
+  /// Check the signature value over the canonical `SignedInfo` with `verifier`,
+  /// then the digest of every reference.
+  pub fn verify(
+    &self,
+    doc: &Document,
+    verifier: &dyn SignatureVerifier,
+  ) -> Result<(), XmlDsigError> {
+    let signed_info = self.canonicalize_signed_info(doc)?;
+    if !verifier.verify(&self.signed_info.signature_method, &signed_info, &self.signature_value) {
+      return Err(XmlDsigError::InvalidSignature);
+    }
+    for reference in &self.signed_info.references {
+      if reference.compute_digest(doc, Some(self.node))? != reference.digest_value {
+        return Err(XmlDsigError::DigestMismatch(
+          reference.uri.clone().unwrap_or_default(),
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  /// Fill in the `DigestValue` of every reference, then the `SignatureValue`
+  /// computed by `signer`, both in `doc` and in `self`.
+  pub fn sign(&mut self, doc: &Document, signer: &dyn Signer) -> Result<(), XmlDsigError> {
+    for (reference, digest_value) in self
+      .signed_info
+      .references
+      .iter_mut()
+      .zip(&self.digest_value_nodes)
+    {
+      reference.digest_value = reference.compute_digest(doc, Some(self.node))?;
+      set_base64(doc, *digest_value, &reference.digest_value)?;
+    }
+    let signed_info = self.canonicalize_signed_info(doc)?;
+    self.signature_value = signer
+      .sign(&self.signed_info.signature_method, &signed_info)
+      .map_err(|_| XmlDsigError::SigningFailed)?;
+    set_base64(doc, self.signature_value_node, &self.signature_value)
+  }
+}
+
+/// All `ds:Signature` elements of `doc`, in document order
+pub fn find_signatures(doc: &Document) -> Vec<RoNode> {
+  let mut signatures = Vec::new();
+  if let Some(root) = doc.get_root_readonly() {
+    find_elements(root, &mut |element| is_dsig_element(element, "Signature"), &mut signatures);
+  }
+  signatures
+}
+
+fn find_elements(node: RoNode, matches: &mut dyn FnMut(RoNode) -> bool, found: &mut Vec<RoNode>) {
+  if matches(node) {
+    found.push(node);
+  }
+  for child in node.get_child_elements() {
+    find_elements(child, matches, found);
+  }
+}
+
+fn has_id(element: RoNode, id: &str) -> bool {
+  ["ID", "Id", "id"]
+    .iter()
+    .any(|name| element.get_attribute_no_ns(name).as_deref() == Some(id))
+    || element.get_attribute_ns("id", XML_NAMESPACE).as_deref() == Some(id)
+}
+
+fn is_ancestor_or_self(ancestor: RoNode, node: RoNode) -> bool {
+  let mut current = Some(node);
+  while let Some(candidate) = current {
+    if candidate == ancestor {
+      return true;
+    }
+    current = candidate.get_parent();
+  }
+  false
+}
+
+fn is_dsig_element(node: RoNode, name: &str) -> bool {
+  is_element_in(node, DSIG_NAMESPACE, name)
+}
+
+fn is_element_in(node: RoNode, namespace: &str, name: &str) -> bool {
+  node.get_type() == Some(NodeType::ElementNode)
+    && node.get_name() == name
+    && node.get_namespace().is_some_and(|ns| ns.get_href() == namespace)
+}
+
+fn child_elements<'a>(
+  node: RoNode,
+  namespace: &'a str,
+  name: &'a str,
+) -> impl Iterator<Item = RoNode> + 'a {
+  node
+    .get_child_elements()
+    .into_iter()
+    .filter(move |child| is_element_in(*child, namespace, name))
+}
+
+fn child_element(node: RoNode, namespace: &str, name: &str) -> Option<RoNode> {
+  child_elements(node, namespace, name).next()
+}
+
+fn algorithm_of(node: RoNode) -> Result<String, XmlDsigError> {
+  node
+    .get_attribute_no_ns("Algorithm")
+    .ok_or(XmlDsigError::Missing("Algorithm"))
+}
+
+fn decode_base64(node: RoNode, what: &'static str) -> Result<Vec<u8>, XmlDsigError> {
+  let encoded: String = node
+    .get_content()
+    .chars()
+    .filter(|c| !c.is_ascii_whitespace())
+    .collect();
+  STANDARD
+    .decode(encoded)
+    .map_err(|_| XmlDsigError::InvalidBase64(what))
+}
+
+fn set_base64(doc: &Document, node: RoNode, value: &[u8]) -> Result<(), XmlDsigError> {
+  doc
+    .register_node(node.node_ptr())
+    .set_content(&STANDARD.encode(value))
+    .map_err(|_| XmlDsigError::SigningFailed)
+}
@@ -2,13 +2,17 @@
 
 use crate::bindings::*;
 use crate::c_helpers::*;
+use crate::error::StructuredError;
 use crate::readonly::RoNode;
-use crate::tree::{Document, DocumentRef, DocumentWeak, Node};
+use crate::schemas::common::structured_error_handler;
+use crate::tree::{Document, DocumentRef, DocumentWeak, Namespace, Node};
 use libc::{c_char, c_void, size_t};
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
 use std::ffi::{CStr, CString};
 use std::fmt;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use std::str;
 
 ///Thinly wrapped libxml2 xpath context
@@ -35,6 +39,59 @@ pub struct Context {
   pub(crate) document: DocumentWeak,
 }
 
+/// How automatic namespace registration treats a prefix that is already bound to a different URI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NamespaceConflict {
+  /// Keep the earliest binding: prefixes already registered on the context win,
+  /// then declarations in the order they are encountered (innermost scope first for
+  /// `register_namespaces_in_scope`, document order for `register_document_namespaces`)
+  #[default]
+  KeepFirst,
+  /// The latest binding replaces any earlier one, including explicit registrations
+  Replace,
+  /// Fail without registering anything if a prefix would be bound to two different URIs
+  Error,
+}
+
+/// Options for registering the namespaces declared in a document on a `Context`
+#[derive(Debug, Clone, Default)]
+pub struct NamespaceRegistration {
+  /// Prefix under which a default namespace (`xmlns="..."`) is registered.
+  /// XPath 1.0 has no default namespace, so such declarations are skipped when this is `None`.
+  pub default_prefix: Option<String>,
+  /// Rule applied when a prefix is bound to more than one URI
+  pub conflict: NamespaceConflict,
+}
+
+/// Errors from evaluating an XPointer
+#[derive(Debug)]
+pub enum XPointerError {
+  /// The pointer contains a NUL byte and can not be handed to libxml2
+  InvalidPointer,
+  /// libxml2 could not create the XPath context to evaluate the pointer in
+  ContextCreationFailed,
+  /// libxml2 rejected the pointer as malformed, or failed to evaluate it.
+  /// Holds the errors it reported, which may be empty.
+  EvaluationFailed(Vec<StructuredError>),
+}
+
+impl Error for XPointerError {}
+
+impl fmt::Display for XPointerError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      XPointerError::InvalidPointer => write!(f, "XPointer contains a NUL byte."),
+      XPointerError::ContextCreationFailed => {
+        write!(f, "Could not create an XPath context for the XPointer.")
+      }
+      XPointerError::EvaluationFailed(errors) => match errors.first().and_then(|e| e.message.as_deref()) {
+        Some(message) => write!(f, "XPointer evaluation failed: {}", message.trim_end()),
+        None => write!(f, "XPointer evaluation failed."),
+      },
+    }
+  }
+}
+
 ///Essentially, the result of the evaluation of some xpath expression
 #[derive(Debug)]
 pub struct Object {
@@ -68,6 +125,20 @@ impl Context {
     }
   }
 
+  /// Context over a document that is not tracked by a `Document` handle,
+  /// such as a `FrozenDocument`. Results can only be read as `RoNode`s or values.
+  pub(crate) fn new_untracked(doc_ptr: xmlDocPtr) -> Result<Context, ()> {
+    let ctxtptr = unsafe { xmlXPathNewContext(doc_ptr) };
+    if ctxtptr.is_null() {
+      Err(())
+    } else {
+      Ok(Context {
+        context_ptr: Rc::new(RefCell::new(_Context(ctxtptr))),
+        document: Weak::new(),
+      })
+    }
+  }
+
   /// Returns the raw libxml2 context pointer behind the struct
   pub fn as_ptr(&self) -> xmlXPathContextPtr {
     self.context_ptr.borrow().0
@@ -98,6 +169,96 @@ impl Context {
     }
   }
 
+  /// Look up the href registered for `prefix` on the xpath context
+  pub fn lookup_namespace(&self, prefix: &str) -> Option<String> {
+    let c_prefix = CString::new(prefix).unwrap();
+    unsafe {
+      let href_ptr = xmlXPathNsLookup(self.as_ptr(), c_prefix.as_bytes().as_ptr());
+      if href_ptr.is_null() {
+        None
+      } else {
+        let c_href = CStr::from_ptr(href_ptr as *const c_char);
+        Some(c_href.to_string_lossy().into_owned())
+      }
+    }
+  }
+
+  /// Register all namespaces in scope at `node` (as reported by `Node::get_namespaces`)
+  pub fn register_namespaces_in_scope(
+    &self,
+    node: &Node,
+    options: &NamespaceRegistration,
+  ) -> Result<(), ()> {
+    self.register_namespaces_in_scope_readonly(RoNode(node.node_ptr()), options)
+  }
+
+  /// Register all namespaces in scope at a read-only `node`
+  pub fn register_namespaces_in_scope_readonly(
+    &self,
+    node: RoNode,
+    options: &NamespaceRegistration,
+  ) -> Result<(), ()> {
+    let namespaces = node.get_namespaces_in(self.doc_ptr());
+    self.register_namespace_list(&namespaces, options)
+  }
+
+  /// Register the namespaces declared anywhere in the context's document,
+  /// walking the element tree in document order
+  pub fn register_document_namespaces(&self, options: &NamespaceRegistration) -> Result<(), ()> {
+    let mut namespaces = Vec::new();
+    let root_ptr = unsafe { xmlDocGetRootElement(self.doc_ptr()) };
+    if !root_ptr.is_null() {
+      collect_namespace_declarations(RoNode(root_ptr), &mut namespaces);
+    }
+    self.register_namespace_list(&namespaces, options)
+  }
+
+  /// The document the libxml2 context was created for
+  fn doc_ptr(&self) -> xmlDocPtr {
+    unsafe { (*self.as_ptr()).doc }
+  }
+
+  /// Resolve all bindings against `options.conflict` first, so that an `Error`
+  /// leaves the context untouched, then register them.
+  fn register_namespace_list(
+    &self,
+    namespaces: &[Namespace],
+    options: &NamespaceRegistration,
+  ) -> Result<(), ()> {
+    let mut bindings: Vec<(String, String)> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for ns in namespaces {
+      let prefix = match ns.get_prefix() {
+        prefix if !prefix.is_empty() => prefix,
+        _ => match options.default_prefix {
+          Some(ref prefix) => prefix.clone(),
+          None => continue,
+        },
+      };
+      let href = ns.get_href();
+      let existing = match seen.get(&prefix) {
+        Some(index) => Some(bindings[*index].1.clone()),
+        None => self.lookup_namespace(&prefix),
+      };
+      match existing {
+        Some(ref bound) if bound == &href => {}
+        Some(_) if options.conflict == NamespaceConflict::KeepFirst => {}
+        Some(_) if options.conflict == NamespaceConflict::Error => return Err(()),
+        _ => match seen.get(&prefix) {
+          Some(index) => bindings[*index].1 = href,
+          None => {
+            seen.insert(prefix.clone(), bindings.len());
+            bindings.push((prefix, href));
+          }
+        },
+      }
+    }
+    for (prefix, href) in bindings {
+      self.register_namespace(&prefix, &href)?;
+    }
+    Ok(())
+  }
+
   ///evaluate an xpath
   pub fn evaluate(&self, xpath: &str) -> Result<Object, ()> {
     let c_xpath = CString::new(xpath).unwrap();
@@ -112,6 +273,37 @@ impl Context {
     }
   }
 
+  /// evaluate an XPointer (the fragment identifier of a reference such as
+  /// `doc.xml#xpointer(//section[2])`, without the `#`) against the context's document.
+  ///
+  /// Supports the schemes implemented by libxml2: bare names (IDs), `element()`,
+  /// `xmlns()` and `xpointer()`/`xpath1()`. The errors libxml2 reports for a
+  /// malformed pointer are returned in `XPointerError::EvaluationFailed`.
+  pub fn evaluate_xpointer(&self, pointer: &str) -> Result<Object, XPointerError> {
+    let c_pointer = CString::new(pointer).map_err(|_| XPointerError::InvalidPointer)?;
+    let mut errlog: Vec<StructuredError> = Vec::new();
+    let ptr = unsafe {
+      let ctxt = self.as_ptr();
+      let previous = ((*ctxt).error, (*ctxt).userData);
+      (*ctxt).error = Some(structured_error_handler);
+      (*ctxt).userData = &mut errlog as *mut Vec<StructuredError> as *mut c_void;
+      let ptr = xmlXPtrEval(c_pointer.as_bytes().as_ptr(), ctxt);
+      ((*ctxt).error, (*ctxt).userData) = previous;
+      ptr
+    };
+    if ptr.is_null() || !errlog.is_empty() {
+      if !ptr.is_null() {
+        unsafe { xmlXPathFreeObject(ptr) };
+      }
+      Err(XPointerError::EvaluationFailed(errlog))
+    } else {
+      Ok(Object {
+        ptr,
+        document: self.document.clone(),
+      })
+    }
+  }
+
   ///evaluate an xpath on a context Node
   pub fn node_evaluate(&self, xpath: &str, node: &Node) -> Result<Object, ()> {
     let c_xpath = CString::new(xpath).unwrap();
@@ -152,6 +344,17 @@ impl Context {
     Ok(())
   }
 
+  /// localize xpath context to a specific RoNode
+  pub fn set_context_node_readonly(&mut self, node: RoNode) -> Result<(), ()> {
+    unsafe {
+      let result = xmlXPathSetContextNode(node.0, self.as_ptr());
+      if result != 0 {
+        return Err(());
+      }
+    }
+    Ok(())
+  }
+
   /// find nodes via xpath, at a specified node or the document root
   pub fn findnodes(&mut self, xpath: &str, node_opt: Option<&Node>) -> Result<Vec<Node>, ()> {
     let evaluated = if let Some(node) = node_opt {
@@ -181,6 +384,44 @@ impl Context {
     };
     Ok(evaluated.to_string())
   }
+
+  /// find read-only nodes via xpath, at a specified node or the document root
+  pub fn findnodes_readonly(
+    &mut self,
+    xpath: &str,
+    node_opt: Option<RoNode>,
+  ) -> Result<Vec<RoNode>, ()> {
+    let evaluated = if let Some(node) = node_opt {
+      self.node_evaluate_readonly(xpath, node)?
+    } else {
+      self.evaluate(xpath)?
+    };
+    Ok(evaluated.get_readonly_nodes_as_vec())
+  }
+
+  /// find literal values via xpath, at a specified read-only node or the document root
+  pub fn findvalues_readonly(
+    &mut self,
+    xpath: &str,
+    node_opt: Option<RoNode>,
+  ) -> Result<Vec<String>, ()> {
+    let evaluated = if let Some(node) = node_opt {
+      self.node_evaluate_readonly(xpath, node)?
+    } else {
+      self.evaluate(xpath)?
+    };
+    Ok(evaluated.get_nodes_as_str())
+  }
+
+  /// find a literal value via xpath, at a specified read-only node or the document root
+  pub fn findvalue_readonly(&mut self, xpath: &str, node_opt: Option<RoNode>) -> Result<String, ()> {
+    let evaluated = if let Some(node) = node_opt {
+      self.node_evaluate_readonly(xpath, node)?
+    } else {
+      self.evaluate(xpath)?
+    };
+    Ok(evaluated.to_string())
+  }
 }
 
 impl Drop for Object {
@@ -192,7 +433,43 @@ impl Drop for Object {
   }
 }
 
+/// The type of value an XPath expression evaluated to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectType {
+  /// A node-set, e.g. from `//foo`
+  NodeSet,
+  /// A boolean, e.g. from `count(//foo) > 1`
+  Boolean,
+  /// A number, e.g. from `count(//foo)`
+  Number,
+  /// A string, e.g. from `string(//foo/@attr)`
+  String,
+  /// Any other libxml2 result type (XSLT result trees, user-defined objects, ...)
+  Other,
+}
+
 impl Object {
+  /// The type of the evaluation result
+  pub fn get_type(&self) -> ObjectType {
+    match unsafe { (*self.ptr).type_ } {
+      crate::bindings::xmlXPathObjectType_XPATH_NODESET => ObjectType::NodeSet,
+      crate::bindings::xmlXPathObjectType_XPATH_BOOLEAN => ObjectType::Boolean,
+      crate::bindings::xmlXPathObjectType_XPATH_NUMBER => ObjectType::Number,
+      crate::bindings::xmlXPathObjectType_XPATH_STRING => ObjectType::String,
+      _ => ObjectType::Other,
+    }
+  }
+
+  /// The result converted to a boolean, following the XPath `boolean()` function
+  pub fn to_boolean(&self) -> bool {
+    unsafe { xmlXPathCastToBoolean(self.ptr) != 0 }
+  }
+
+  /// The result converted to a number, following the XPath `number()` function
+  pub fn to_number(&self) -> f64 {
+    unsafe { xmlXPathCastToNumber(self.ptr) }
+  }
+
   ///get the number of nodes in the result set
   pub fn get_number_of_nodes(&self) -> usize {
     let v = xmlXPathObjectNumberOfNodes(self.ptr);
@@ -283,6 +560,13 @@ impl fmt::Display for Object {
   }
 }
 
+fn collect_namespace_declarations(node: RoNode, namespaces: &mut Vec<Namespace>) {
+  namespaces.extend(node.get_namespace_declarations());
+  for child in node.get_child_elements() {
+    collect_namespace_declarations(child, namespaces);
+  }
+}
+
 /// Calls the binding to http://xmlsoft.org/html/libxml-xpath.html#xmlXPathCompile and return true if
 /// a non-null pointer is returned. The idea is to use this to validate an xpath independent of context.
 /// Tests describing what this validates in tests/xpath_tests.rs
@@ -1,5 +1,9 @@
+use std::io::{self, Write};
+
 use libxml::parser::Parser;
-use libxml::tree::c14n::{CanonicalizationMode, CanonicalizationOptions};
+use libxml::readonly::RoNode;
+use libxml::tree::c14n::{CanonicalizationFilter, CanonicalizationMode, CanonicalizationOptions};
+use libxml::tree::NodeType;
 
 fn assert_eq_lines(seen: &str, expected: &str) {
     let lines_iter = seen.lines().zip(expected.lines());
@@ -245,6 +249,182 @@ fn test_c14n_modes() {
   assert_eq_lines(expected, &c14n);
 }
 
+/// A `Write` standing in for a hasher: sees the output in chunks, keeps no copy
+#[derive(Default)]
+struct DigestWriter {
+  len: usize,
+  checksum: u64,
+}
+
+impl Write for DigestWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.len += buf.len();
+    for byte in buf {
+      self.checksum = self.checksum.wrapping_mul(31).wrapping_add(u64::from(*byte));
+    }
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+#[test]
+fn test_c14n_streamed_to_writer() {
+  let input = include_str!("resources/canonical_exclusive/1_input.xml");
+  let doc = Parser::default().parse_string(input).unwrap();
+  let options = CanonicalizationOptions::default();
+
+  let bytes = doc.canonicalize_to_bytes(options.clone()).unwrap();
+  assert_eq!(
+    bytes,
+    doc.canonicalize(options.clone(), None).unwrap().into_bytes()
+  );
+
+  let mut digest = DigestWriter::default();
+  doc.canonicalize_to(&mut digest, options.clone()).unwrap();
+  let mut expected = DigestWriter::default();
+  expected.write_all(&bytes).unwrap();
+  assert_eq!(digest.len, bytes.len());
+  assert_eq!(digest.checksum, expected.checksum);
+
+  // multi-byte characters come out as-is, whatever the chunking
+  let text = "é".repeat(10_000);
+  let doc = Parser::default()
+    .parse_string(format!("<a>{text}</a>"))
+    .unwrap();
+  let bytes = doc.canonicalize_to_bytes(opts()).unwrap();
+  assert_eq!(bytes, format!("<a>{text}</a>").into_bytes());
+}
+
+#[test]
+fn test_c14n_node_streamed_to_writer() {
+  let xml = "<a><b><c>ü</c></b></a>";
+  let doc = Parser::default().parse_string(xml).unwrap();
+  let node = doc.as_node().findnodes("//b").unwrap().pop().unwrap();
+
+  assert_eq!(
+    node.canonicalize_to_bytes(opts()).unwrap(),
+    "<b><c>ü</c></b>".as_bytes()
+  );
+  let mut output = Vec::new();
+  node.canonicalize_to(&mut output, opts()).unwrap();
+  assert_eq!(output, "<b><c>ü</c></b>".as_bytes());
+}
+
+#[test]
+fn test_c14n_writer_error() {
+  struct Rejecting;
+  impl Write for Rejecting {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+      Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  let doc = Parser::default().parse_string("<a><b/></a>").unwrap();
+  let error = doc.canonicalize_to(Rejecting, opts()).unwrap_err();
+  assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+}
+
+const SIGNED: &str = r#"<doc xmlns="urn:doc" xmlns:ds="http://www.w3.org/2000/09/xmldsig#" id="d1"><item n="1">one</item><ds:Signature><ds:SignedInfo/></ds:Signature><item n="2">two</item></doc>"#;
+
+const UNSIGNED_C14N: &str = r#"<doc xmlns="urn:doc" id="d1"><item n="1">one</item><item n="2">two</item></doc>"#;
+
+fn in_signature(node: RoNode) -> bool {
+  let mut current = Some(node);
+  while let Some(node) = current {
+    if node.get_name() == "Signature" {
+      return true;
+    }
+    current = node.get_parent();
+  }
+  false
+}
+
+#[test]
+fn test_c14n_closure_filter() {
+  let doc = Parser::default().parse_string(SIGNED).unwrap();
+  let not_in_signature = |node: RoNode, _parent: Option<RoNode>| !in_signature(node);
+
+  let c14n = doc
+    .canonicalize_filtered(
+      CanonicalizationOptions::default(),
+      CanonicalizationFilter::Visible(&not_in_signature),
+    )
+    .unwrap();
+  assert_eq!(c14n, UNSIGNED_C14N);
+
+  // attributes are offered with their element as parent
+  let no_attributes = |node: RoNode, parent: Option<RoNode>| {
+    node.get_type() != Some(NodeType::AttributeNode) || parent.is_none()
+  };
+  let mut output = Vec::new();
+  doc
+    .canonicalize_filtered_to(
+      &mut output,
+      CanonicalizationOptions::default(),
+      CanonicalizationFilter::Visible(&no_attributes),
+    )
+    .unwrap();
+  assert!(!String::from_utf8(output).unwrap().contains("n=\"1\""));
+}
+
+#[test]
+fn test_c14n_xpath_filter() {
+  let doc = Parser::default().parse_string(SIGNED).unwrap();
+  let namespaces = [("ds", "http://www.w3.org/2000/09/xmldsig#")];
+
+  let c14n = doc
+    .canonicalize_filtered(
+      CanonicalizationOptions::default(),
+      CanonicalizationFilter::XPath {
+        expression: "(//. | //@* | //namespace::*)[not(ancestor-or-self::ds:Signature)]",
+        namespaces: &namespaces,
+      },
+    )
+    .unwrap();
+  assert_eq!(c14n, UNSIGNED_C14N);
+
+  // only selected nodes are output: no attributes selected, none kept
+  let c14n = doc
+    .canonicalize_filtered(
+      CanonicalizationOptions::default(),
+      CanonicalizationFilter::XPath {
+        expression: "//*[local-name() = 'item'] | //*[local-name() = 'item']/text()",
+        namespaces: &[],
+      },
+    )
+    .unwrap();
+  assert_eq!(c14n, "<item>one</item><item>two</item>");
+
+  let empty = doc
+    .canonicalize_filtered(
+      CanonicalizationOptions::default(),
+      CanonicalizationFilter::XPath {
+        expression: "//nothing",
+        namespaces: &[],
+      },
+    )
+    .unwrap();
+  assert_eq!(empty, "");
+
+  let error = doc
+    .canonicalize_filtered_to(
+      Vec::new(),
+      CanonicalizationOptions::default(),
+      CanonicalizationFilter::XPath {
+        expression: "count(//*)",
+        namespaces: &[],
+      },
+    )
+    .unwrap_err();
+  assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
 fn opts() -> CanonicalizationOptions {
   CanonicalizationOptions {
     mode: CanonicalizationMode::Canonical1_1,
@@ -0,0 +1,127 @@
+//! Catalog tests
+//!
+use libxml::catalog::{Catalog, CatalogEntry};
+use libxml::parser::{Parser, ParserOptions};
+
+const CATALOG: &str = "tests/resources/catalog/catalog.xml";
+
+#[test]
+/// Identifiers and URIs resolve to the local copies listed in the catalog
+fn catalog_resolution() {
+  let catalog = Catalog::load(CATALOG).unwrap();
+  assert!(!catalog.is_empty());
+
+  let greeting = catalog
+    .resolve_public("-//EXAMPLE//DTD Greeting 1.0//EN")
+    .unwrap();
+  assert!(greeting.ends_with("tests/resources/catalog/greeting.dtd"));
+  let farewell = catalog
+    .resolve_system("http://example.com/dtd/farewell.dtd")
+    .unwrap();
+  assert!(farewell.ends_with("tests/resources/catalog/farewell.dtd"));
+  let letter = catalog
+    .resolve_system("http://example.com/schemas/letter.dtd")
+    .unwrap();
+  assert!(letter.ends_with("tests/resources/catalog/schemas/letter.dtd"));
+  let style = catalog
+    .resolve_uri("http://example.com/xsl/style.xsl")
+    .unwrap();
+  assert!(style.ends_with("tests/resources/catalog/style.xsl"));
+
+  assert_eq!(
+    catalog.resolve(
+      Some("-//EXAMPLE//DTD Greeting 1.0//EN"),
+      Some("http://example.com/unknown.dtd")
+    ),
+    Some(greeting)
+  );
+  assert_eq!(catalog.resolve_system("http://example.com/unknown.dtd"), None);
+  assert_eq!(catalog.resolve_public("-//EXAMPLE//DTD Unknown//EN"), None);
+  assert_eq!(catalog.resolve(None, None), None);
+}
+
+#[test]
+/// Entries added at runtime take part in resolution and can be removed
+fn catalog_programmatic_entries() {
+  let mut catalog = Catalog::load("tests/resources/catalog/empty.xml").unwrap();
+  assert!(catalog.resolve_system("urn:x:greeting").is_none());
+
+  catalog
+    .add(CatalogEntry::System, "urn:x:greeting", "/opt/dtd/greeting.dtd")
+    .unwrap();
+  catalog
+    .add(CatalogEntry::Public, "-//X//Farewell//EN", "/opt/dtd/farewell.dtd")
+    .unwrap();
+  catalog
+    .add(CatalogEntry::RewriteUri, "http://x.test/xsl/", "/opt/xsl/")
+    .unwrap();
+  assert!(!catalog.is_empty());
+  assert_eq!(
+    catalog.resolve_system("urn:x:greeting").as_deref(),
+    Some("/opt/dtd/greeting.dtd")
+  );
+  assert_eq!(
+    catalog.resolve_public("-//X//Farewell//EN").as_deref(),
+    Some("/opt/dtd/farewell.dtd")
+  );
+  assert_eq!(
+    catalog.resolve_uri("http://x.test/xsl/main.xsl").as_deref(),
+    Some("/opt/xsl/main.xsl")
+  );
+
+  catalog.remove("urn:x:greeting").unwrap();
+  assert!(catalog.resolve_system("urn:x:greeting").is_none());
+
+  assert!(Catalog::load("tests/resources/catalog/missing.xml").is_err());
+}
+
+#[test]
+/// A catalog attached to a parse loads the external DTDs offline
+fn catalog_attached_to_parse() {
+  let catalog = Catalog::load(CATALOG).unwrap();
+  let parse = |input: &str, catalog: Option<&Catalog>| {
+    let options = ParserOptions {
+      dtd_load: true,
+      no_ent: true,
+      no_net: true,
+      catalog,
+      ..ParserOptions::default()
+    };
+    Parser::default()
+      .parse_string_with_options(input, options)
+      .unwrap()
+  };
+
+  let by_public = r#"<!DOCTYPE greeting PUBLIC "-//EXAMPLE//DTD Greeting 1.0//EN"
+    "http://example.com/dtd/greeting.dtd"><greeting>hello &who;</greeting>"#;
+  let doc = parse(by_public, Some(&catalog));
+  assert_eq!(doc.get_root_element().unwrap().get_content(), "hello world");
+
+  let by_system = r#"<!DOCTYPE farewell SYSTEM "http://example.com/dtd/farewell.dtd">
+    <farewell>bye &who;</farewell>"#;
+  let doc = parse(by_system, Some(&catalog));
+  assert_eq!(doc.get_root_element().unwrap().get_content(), "bye everyone");
+
+  // rewritten system identifiers
+  let rewritten = r#"<!DOCTYPE letter SYSTEM "http://example.com/schemas/letter.dtd">
+    <letter>dear &who;</letter>"#;
+  let doc = parse(rewritten, Some(&catalog));
+  let letter = doc.get_root_element().unwrap();
+  assert_eq!(letter.get_content(), "dear reader");
+
+  // without the catalog, no_net keeps libxml2 from fetching the DTD
+  let doc = parse(by_public, None);
+  assert_eq!(doc.get_root_element().unwrap().get_content(), "hello ");
+
+  // the catalog alone does not load the external subset
+  let options = ParserOptions {
+    no_ent: true,
+    no_net: true,
+    catalog: Some(&catalog),
+    ..ParserOptions::default()
+  };
+  let doc = Parser::default()
+    .parse_string_with_options(by_public, options)
+    .unwrap();
+  assert_eq!(doc.get_root_element().unwrap().get_content(), "hello ");
+}
@@ -0,0 +1,173 @@
+//! CSS selector tests
+//!
+use libxml::css::to_xpath;
+use libxml::parser::Parser;
+use libxml::tree::Node;
+
+const HTML: &str = r#"<html><body>
+<div id="main" class="content  wide">
+  <h1 lang="en-US">Title</h1>
+  <p class="lead">one</p>
+  <p>two <a href="https://example.com/a.pdf" data-kind="doc link">pdf</a></p>
+  <ul>
+    <li class="item">1</li><li class="item active">2</li><li class="item">3</li>
+    <li class="item">4</li><li class="item-extra">5</li>
+  </ul>
+  <p></p>
+</div>
+<div class="sidebar"><p class="lead">side</p></div>
+</body></html>"#;
+
+fn texts(nodes: &[Node]) -> Vec<String> {
+  nodes.iter().map(|node| node.get_content()).collect()
+}
+
+#[test]
+fn select_simple_selectors() {
+  let doc = Parser::default_html().parse_string(HTML).unwrap();
+  let root = doc.get_root_element().unwrap();
+
+  assert_eq!(root.select("li").unwrap().len(), 5);
+  assert_eq!(root.select("*").unwrap().len(), 15);
+  let main = root.select_first("#main").unwrap().unwrap();
+  assert_eq!(main.get_name(), "div");
+  assert_eq!(texts(&root.select("p.lead").unwrap()), ["one", "side"]);
+  assert_eq!(texts(&root.select(".wide h1").unwrap()), ["Title"]);
+
+  // class selectors match the names of `get_class_names`
+  let items = root.select(".item").unwrap();
+  assert_eq!(texts(&items), ["1", "2", "3", "4"]);
+  for li in root.select("li").unwrap() {
+    assert_eq!(
+      li.get_class_names().contains("item"),
+      items.contains(&li)
+    );
+  }
+  assert_eq!(texts(&root.select("li.item.active").unwrap()), ["2"]);
+  assert!(root.select_first(".missing").unwrap().is_none());
+}
+
+#[test]
+fn select_attributes() {
+  let doc = Parser::default_html().parse_string(HTML).unwrap();
+  let root = doc.get_root_element().unwrap();
+
+  assert_eq!(texts(&root.select("[href]").unwrap()), ["pdf"]);
+  assert_eq!(texts(&root.select("[href$='.pdf']").unwrap()), ["pdf"]);
+  assert_eq!(texts(&root.select("a[href^=\"https:\"]").unwrap()), ["pdf"]);
+  assert_eq!(texts(&root.select("[href*=example]").unwrap()), ["pdf"]);
+  assert_eq!(texts(&root.select("[data-kind~=link]").unwrap()), ["pdf"]);
+  assert!(root.select("[data-kind~=lin]").unwrap().is_empty());
+  assert_eq!(texts(&root.select("[lang|=en]").unwrap()), ["Title"]);
+  assert!(root.select("[lang|=e]").unwrap().is_empty());
+  assert_eq!(texts(&root.select("[class=lead]").unwrap()), ["one", "side"]);
+  assert!(root.select("[href^='']").unwrap().is_empty());
+}
+
+#[test]
+fn select_combinators() {
+  let doc = Parser::default_html().parse_string(HTML).unwrap();
+  let root = doc.get_root_element().unwrap();
+
+  assert_eq!(root.select("div > p").unwrap().len(), 4);
+  assert_eq!(root.select("body > p").unwrap().len(), 0);
+  assert_eq!(texts(&root.select("h1 + p").unwrap()), ["one"]);
+  assert_eq!(texts(&root.select("li.active ~ li").unwrap()), ["3", "4", "5"]);
+  assert_eq!(texts(&root.select("li.active+li").unwrap()), ["3"]);
+  assert_eq!(
+    texts(&root.select("#main > h1, .sidebar p").unwrap()),
+    ["Title", "side"]
+  );
+  // matches are limited to the descendants of the node
+  let sidebar = root.select_first(".sidebar").unwrap().unwrap();
+  assert_eq!(texts(&sidebar.select("p").unwrap()), ["side"]);
+  assert!(sidebar.select("div p").unwrap().is_empty());
+}
+
+#[test]
+fn select_pseudo_classes() {
+  let doc = Parser::default_html().parse_string(HTML).unwrap();
+  let root = doc.get_root_element().unwrap();
+
+  assert_eq!(texts(&root.select("li:first-child").unwrap()), ["1"]);
+  assert_eq!(texts(&root.select("li:last-child").unwrap()), ["5"]);
+  assert_eq!(texts(&root.select("li:nth-child(2)").unwrap()), ["2"]);
+  assert_eq!(texts(&root.select("li:nth-child(odd)").unwrap()), ["1", "3", "5"]);
+  assert_eq!(texts(&root.select("li:nth-child(2n)").unwrap()), ["2", "4"]);
+  assert_eq!(texts(&root.select("li:nth-child( -n + 2 )").unwrap()), ["1", "2"]);
+  assert_eq!(texts(&root.select("li:nth-child(3n+1)").unwrap()), ["1", "4"]);
+  assert_eq!(texts(&root.select("li:nth-last-child(1)").unwrap()), ["5"]);
+  assert_eq!(texts(&root.select("li:not(.item)").unwrap()), ["5"]);
+  assert_eq!(
+    texts(&root.select("li:not(.item.active):not(:last-child)").unwrap()),
+    ["1", "3", "4"]
+  );
+  assert_eq!(root.select("#main > p:empty").unwrap().len(), 1);
+  assert_eq!(texts(&root.select(".sidebar p:only-child").unwrap()), ["side"]);
+}
+
+#[test]
+fn select_readonly() {
+  let doc = Parser::default_html().parse_string(HTML).unwrap();
+  let root = doc.get_root_readonly().unwrap();
+  let found = root.select("ul li.active", &doc).unwrap();
+  assert_eq!(found.len(), 1);
+  assert_eq!(found[0].get_content(), "2");
+  let first = root.select_first("p", &doc).unwrap().unwrap();
+  assert_eq!(first.get_content(), "one");
+}
+
+#[test]
+fn invalid_selectors() {
+  for selector in ["", "p >", "p:hover", "[href", "li:nth-child(x)", "a::before", "p,", "'a'"] {
+    assert!(to_xpath(selector).is_err(), "{selector}");
+  }
+  let error = to_xpath("div:visited").unwrap_err();
+  assert_eq!(error.position, 11);
+  assert_eq!(error.to_string(), "unsupported pseudo-class :visited at position 11");
+
+  let doc = Parser::default_html().parse_string(HTML).unwrap();
+  assert!(doc.get_root_element().unwrap().select("p[").is_err());
+
+  // quotes in values
+  assert_eq!(
+    to_xpath(r#"[title="it's"]"#).unwrap(),
+    r#"descendant::*[@title="it's"]"#
+  );
+  assert_eq!(
+    to_xpath(r#"[title='"it\'s"']"#).unwrap(),
+    r#"descendant::*[@title=concat('"it', "'", 's"')]"#
+  );
+}
+
+#[test]
+fn attribute_names_are_validated() {
+  // an escaped operator in the name must not reach the XPath expression
+  for selector in [r"[a\ or\ true()]", r"[a\]\|\|@b]", r"[\31 a]", r"[a\:b]"] {
+    let error = to_xpath(selector).unwrap_err();
+    assert_eq!(error.position, 1, "{selector}");
+    assert!(error.message.starts_with("invalid attribute name"), "{selector}");
+  }
+  assert_eq!(to_xpath(r"[data\-kind]").unwrap(), "descendant::*[@data-kind]");
+
+  let doc = Parser::default_html().parse_string(HTML).unwrap();
+  let root = doc.get_root_element().unwrap();
+  let error = root.select(r"[a\ or\ true()]").unwrap_err();
+  assert_eq!(error.to_string(), "invalid attribute name 'a or true' at position 1");
+  let readonly = doc.get_root_readonly().unwrap();
+  assert_eq!(readonly.select(r"[a\ or\ true()]", &doc).unwrap_err(), error);
+}
+
+#[test]
+fn html_names_are_case_insensitive() {
+  let doc = Parser::default_html().parse_string(HTML).unwrap();
+  let root = doc.get_root_element().unwrap();
+  assert_eq!(texts(&root.select("UL > LI.active").unwrap()), ["2"]);
+  assert_eq!(texts(&root.select("A[HREF$='.pdf']").unwrap()), ["pdf"]);
+  // class values and XML documents stay case-sensitive
+  assert!(root.select("li.ACTIVE").unwrap().is_empty());
+  let xml = Parser::default().parse_string("<r><Item/><item/></r>").unwrap();
+  let xml_root = xml.get_root_element().unwrap();
+  assert_eq!(xml_root.select("Item").unwrap().len(), 1);
+  assert_eq!(xml_root.select("ITEM").unwrap().len(), 0);
+}
@@ -0,0 +1,78 @@
+//! pattern module tests
+//!
+
+use libxml::parser::Parser;
+use libxml::pattern::Pattern;
+use libxml::readonly::RoNode;
+
+fn count_matches(node: RoNode, pattern: &Pattern) -> usize {
+  let own = usize::from(pattern.matches_readonly(node));
+  own
+    + node
+      .get_child_elements()
+      .into_iter()
+      .map(|child| count_matches(child, pattern))
+      .sum::<usize>()
+}
+
+#[test]
+/// Patterns select the same elements as the equivalent XPath
+fn pattern_matches_nodes() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_file("tests/resources/simple_namespaces.xml")
+    .unwrap();
+  let root = doc.get_root_readonly().unwrap();
+  let namespaces = [
+    ("h", "http://example.com/ns/hello"),
+    ("f", "http://example.com/ns/farewell"),
+  ];
+
+  let td = Pattern::new("h:td", &namespaces).unwrap();
+  assert_eq!(count_matches(root, &td), 3);
+  let nested = Pattern::new("f:footer//f:footer", &namespaces).unwrap();
+  assert_eq!(count_matches(root, &nested), 1);
+  let union = Pattern::new("h:table | f:mock", &namespaces).unwrap();
+  assert_eq!(count_matches(root, &union), 3);
+
+  let root_node = doc.get_root_element().unwrap();
+  assert!(Pattern::new("/*", &[]).unwrap().matches(&root_node));
+  assert!(!td.matches(&root_node));
+}
+
+#[test]
+fn pattern_rejects_invalid_input() {
+  assert!(Pattern::new("//a[1]", &[]).is_err());
+  assert!(Pattern::new("undeclared:a", &[]).is_err());
+}
+
+#[test]
+/// Streaming evaluation only needs start/end element events
+fn pattern_streaming() {
+  let pattern = Pattern::new("/feed/entry/title", &[]).unwrap();
+  assert!(pattern.is_streamable());
+  assert_eq!(pattern.max_depth(), Some(3));
+
+  let mut stream = pattern.stream().unwrap();
+  assert!(stream.push_document().is_ok());
+  assert_eq!(stream.push("feed", None), Ok(false));
+  assert_eq!(stream.push("entry", None), Ok(false));
+  assert_eq!(stream.push("title", None), Ok(true));
+  assert!(stream.pop().is_ok());
+  assert_eq!(stream.push("summary", None), Ok(false));
+  assert!(stream.pop().is_ok());
+  assert!(stream.pop().is_ok());
+  assert_eq!(stream.push("entry", None), Ok(false));
+  assert_eq!(stream.push("title", None), Ok(true));
+
+  let deep = Pattern::new("//x:item/@id", &[("x", "urn:x")]).unwrap();
+  assert_eq!(deep.max_depth(), None);
+  let mut stream = deep.stream().unwrap();
+  assert_eq!(stream.push("wrapper", None), Ok(false));
+  assert_eq!(stream.push("item", Some("urn:x")), Ok(false));
+  assert_eq!(stream.push_attribute("id", None), Ok(true));
+  assert_eq!(stream.push_attribute("id", Some("urn:x")), Ok(false));
+  assert!(stream.pop().is_ok());
+  assert_eq!(stream.push("item", None), Ok(false));
+  assert_eq!(stream.push_attribute("id", None), Ok(false));
+}
@@ -1,8 +1,10 @@
 //! Tree module tests
 //!
 use libxml::parser::Parser;
+use rayon::prelude::*;
 use libxml::readonly::RoNode;
 use libxml::tree::NodeType;
+use libxml::xpath::{Context, NamespaceRegistration, ObjectType};
 
 fn dfs_node(node: RoNode) -> i32 {
   1 + node
@@ -57,3 +59,152 @@ fn readonly_scan_test() {
   assert_eq!(sibling.get_type(), Some(NodeType::ElementNode));
   assert_eq!(sibling.get_name(), "body");
 }
+
+#[test]
+fn readonly_xpath_api() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_file("tests/resources/simple_namespaces.xml")
+    .unwrap();
+  let root: RoNode = doc.get_root_readonly().unwrap();
+
+  let tds = root
+    .findvalues(".//*[local-name()='td']", &doc)
+    .unwrap();
+  assert_eq!(tds, vec!["col 1", "col 2", "col 3"]);
+  assert_eq!(
+    root.findvalue("count(.//*[local-name()='td'])", &doc),
+    Ok(String::from("3"))
+  );
+
+  let bindings = [("h", "http://example.com/ns/hello")];
+  let first_td = root.at_xpath(".//h:td", &bindings, &doc).unwrap();
+  assert_eq!(first_td.map(|td| td.get_content()), Some(String::from("col 1")));
+  assert_eq!(root.at_xpath(".//h:missing", &bindings, &doc), Ok(None));
+  assert!(root.at_xpath(".//h:td", &[], &doc).is_err());
+
+  let count = root.evaluate("count(.//*)", &doc).unwrap();
+  assert_eq!(count.get_type(), ObjectType::Number);
+  assert_eq!(count.to_number(), 11.0);
+  let has_tables = root.evaluate("count(.//*[local-name()='table']) = 2", &doc).unwrap();
+  assert_eq!(has_tables.get_type(), ObjectType::Boolean);
+  assert!(has_tables.to_boolean());
+}
+
+#[test]
+fn readonly_xpath_context() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_file("tests/resources/simple_namespaces.xml")
+    .unwrap();
+  let root: RoNode = doc.get_root_readonly().unwrap();
+  let mut context = Context::new(&doc).unwrap();
+  context
+    .register_namespaces_in_scope_readonly(root, &NamespaceRegistration::default())
+    .unwrap();
+
+  let footers = context.findnodes_readonly("//f:footer", None).unwrap();
+  assert_eq!(footers.len(), 2);
+  let nested = context
+    .findvalues_readonly(".//f:footer", Some(footers[0]))
+    .unwrap();
+  assert_eq!(nested, vec![" nested f"]);
+
+  context.set_context_node_readonly(footers[1]).unwrap();
+  assert_eq!(context.findvalue_readonly("name(..)", None), Ok(String::from("h:tr")));
+}
+
+#[test]
+fn frozen_document_parallel_xpath() {
+  let parser = Parser::default();
+  let doc = parser.parse_file("tests/resources/large_doc.xml").unwrap();
+  let Ok(frozen) = doc.freeze() else {
+    panic!("no other handles on the document");
+  };
+  let options = NamespaceRegistration {
+    default_prefix: Some(String::from("ltx")),
+    ..NamespaceRegistration::default()
+  };
+
+  let expected = {
+    let mut context = frozen.xpath_context().unwrap();
+    context.register_document_namespaces(&options).unwrap();
+    context.findnodes("//ltx:para", None).unwrap().len()
+  };
+  assert!(expected > 0);
+
+  let counts: Vec<usize> = (0..32)
+    .into_par_iter()
+    .map(|_| {
+      let mut context = frozen.xpath_context().unwrap();
+      context.register_document_namespaces(&options).unwrap();
+      context.findnodes("//ltx:para", None).unwrap().len()
+    })
+    .collect();
+  assert!(counts.iter().all(|count| *count == expected));
+
+  // per-thread contexts evaluating relative to shared read-only nodes
+  let sections = {
+    let mut context = frozen.xpath_context().unwrap();
+    context.register_document_namespaces(&options).unwrap();
+    context.findnodes("//ltx:section", None).unwrap()
+  };
+  let para_total: usize = sections
+    .par_iter()
+    .map_init(
+      || {
+        let context = frozen.xpath_context().unwrap();
+        context.register_document_namespaces(&options).unwrap();
+        context
+      },
+      |context, section| context.findnodes("./ltx:para", Some(*section)).unwrap().len(),
+    )
+    .sum();
+  let sequential_total: usize = sections
+    .iter()
+    .map(|section| section.get_child_elements().iter().filter(|c| c.get_name() == "para").count())
+    .sum();
+  assert!(para_total > 0);
+  assert_eq!(para_total, sequential_total);
+
+  let doc = frozen.thaw();
+  assert!(doc.get_root_element().is_some());
+}
+
+#[test]
+fn freeze_requires_exclusive_document() {
+  let parser = Parser::default();
+  let doc = parser.parse_file("tests/resources/file01.xml").unwrap();
+
+  let clone = doc.clone();
+  let doc = doc.freeze().expect_err("a document clone is alive");
+  drop(clone);
+
+  let root = doc.get_root_element().unwrap();
+  let doc = doc.freeze().expect_err("a node wrapper is alive");
+  drop(root);
+
+  let Ok(frozen) = doc.freeze() else {
+    panic!("document is exclusively owned");
+  };
+  let mut context = frozen.xpath_context().unwrap();
+  assert_eq!(context.findvalues("//child", None).unwrap().len(), 2);
+}
+
+#[test]
+fn frozen_evaluate_offers_readonly_nodes() {
+  let parser = Parser::default();
+  let doc = parser.parse_file("tests/resources/file01.xml").unwrap();
+  let Ok(frozen) = doc.freeze() else {
+    panic!("document is exclusively owned");
+  };
+  let context = frozen.xpath_context().unwrap();
+  let result = context.evaluate("//child", None).unwrap();
+  assert_eq!(result.get_type(), ObjectType::NodeSet);
+  assert_eq!(result.get_number_of_nodes(), 2);
+  assert_eq!(result.get_readonly_nodes_as_vec().len(), 2);
+  assert_eq!(result.get_nodes_as_str().len(), 2);
+  let count = context.evaluate("count(//child)", None).unwrap();
+  assert_eq!(count.to_number(), 2.0);
+  assert_eq!(count.to_string(), "2");
+}
@@ -0,0 +1,374 @@
+//! Serialization tests
+//!
+use std::io::{self, Write};
+
+use libxml::parser::Parser;
+use libxml::tree::{OutputOptions, SaveOptions};
+
+/// A writer that accepts `capacity` bytes, then fails with a custom error
+struct FailingWriter {
+  written: Vec<u8>,
+  capacity: usize,
+}
+
+impl Write for FailingWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    if self.written.len() + buf.len() > self.capacity {
+      return Err(io::Error::new(io::ErrorKind::BrokenPipe, "peer went away"));
+    }
+    self.written.extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+#[test]
+/// Streaming into a writer produces the same bytes as the in-memory serializers
+fn write_to_matches_string_serialization() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_file("tests/resources/simple_namespaces.xml")
+    .unwrap();
+
+  for options in [
+    SaveOptions::default(),
+    SaveOptions {
+      format: true,
+      no_declaration: true,
+      ..SaveOptions::default()
+    },
+  ] {
+    let mut output = Vec::new();
+    doc.write_to(&mut output, options).unwrap();
+    assert_eq!(
+      String::from_utf8(output).unwrap(),
+      doc.to_string_with_options(options)
+    );
+  }
+
+  let table = doc.get_root_element().unwrap().get_first_element_child().unwrap();
+  let mut output = Vec::new();
+  doc
+    .write_node_to(&table, &mut output, SaveOptions::default())
+    .unwrap();
+  let serialized = String::from_utf8(output).unwrap();
+  assert!(serialized.starts_with("<h:table>"));
+  assert!(serialized.ends_with("</h:table>"));
+
+  let table = doc
+    .get_root_readonly()
+    .unwrap()
+    .get_first_element_child()
+    .unwrap();
+  let mut readonly_output = Vec::new();
+  doc
+    .write_ronode_to(&table, &mut readonly_output, SaveOptions::default())
+    .unwrap();
+  assert_eq!(String::from_utf8(readonly_output).unwrap(), serialized);
+}
+
+#[test]
+/// The writer's own error is returned, not a generic libxml2 failure
+fn write_to_propagates_io_errors() {
+  let parser = Parser::default();
+  let doc = parser.parse_file("tests/resources/large_doc.xml").unwrap();
+
+  let mut writer = FailingWriter {
+    written: Vec::new(),
+    capacity: 10_000,
+  };
+  let error = doc
+    .write_to(&mut writer, SaveOptions::default())
+    .unwrap_err();
+  assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+  assert_eq!(error.to_string(), "peer went away");
+  assert!(writer.written.len() <= 10_000);
+
+  let mut unlimited = FailingWriter {
+    written: Vec::new(),
+    capacity: usize::MAX,
+  };
+  assert!(doc.write_to(&mut unlimited, SaveOptions::default()).is_ok());
+  assert!(unlimited.written.len() > 10_000);
+}
+
+#[test]
+/// Node serialization honors the save flags that `node_to_string` ignores
+fn node_serialization_with_options() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_string("<root><list><item/><item>text</item></list></root>")
+    .unwrap();
+  let list = doc.get_root_element().unwrap().get_first_element_child().unwrap();
+
+  assert_eq!(
+    doc.node_to_string(&list),
+    "<list><item/><item>text</item></list>"
+  );
+  let formatted = SaveOptions {
+    format: true,
+    ..SaveOptions::default()
+  };
+  assert_eq!(
+    doc.node_to_string_with_options(&list, formatted).unwrap(),
+    "<list>\n  <item/>\n  <item>text</item>\n</list>"
+  );
+  let no_empty_tags = SaveOptions {
+    no_empty_tags: true,
+    ..SaveOptions::default()
+  };
+  assert_eq!(
+    doc.node_to_string_with_options(&list, no_empty_tags).unwrap(),
+    "<list><item></item><item>text</item></list>"
+  );
+  let ro_list = doc
+    .get_root_readonly()
+    .unwrap()
+    .get_first_element_child()
+    .unwrap();
+  assert_eq!(
+    doc.ronode_to_string_with_options(&ro_list, formatted).unwrap(),
+    doc.node_to_string_with_options(&list, formatted).unwrap()
+  );
+
+  let html = parser.parse_string("<p><br/><img src='a'/></p>").unwrap();
+  let p = html.get_root_element().unwrap();
+  let as_html = SaveOptions {
+    as_html: true,
+    ..SaveOptions::default()
+  };
+  assert_eq!(
+    html.node_to_string_with_options(&p, as_html).unwrap(),
+    "<p><br><img src=\"a\"></p>"
+  );
+}
+
+#[test]
+/// Per-call indentation string, restored afterwards
+fn serialization_indent_string() {
+  let parser = Parser::default();
+  let doc = parser.parse_string("<a><b><c/></b></a>").unwrap();
+  let tabs = OutputOptions {
+    save: SaveOptions {
+      format: true,
+      no_declaration: true,
+      ..SaveOptions::default()
+    },
+    indent: Some("\t"),
+    ..OutputOptions::default()
+  };
+  assert_eq!(
+    doc.to_bytes_with_options(&tabs).unwrap(),
+    b"<a>\n\t<b>\n\t\t<c/>\n\t</b>\n</a>\n"
+  );
+  let root = doc.get_root_element().unwrap();
+  let flat = OutputOptions {
+    indent: Some(""),
+    ..tabs
+  };
+  assert_eq!(
+    doc.node_to_bytes_with_options(&root, &flat).unwrap(),
+    b"<a>\n<b>\n<c/>\n</b>\n</a>"
+  );
+  // the process-wide default is untouched
+  let formatted = SaveOptions {
+    format: true,
+    no_declaration: true,
+    ..SaveOptions::default()
+  };
+  assert_eq!(
+    doc.to_string_with_options(formatted),
+    "<a>\n  <b>\n    <c/>\n  </b>\n</a>\n"
+  );
+}
+
+#[test]
+/// Output in encodings other than UTF-8
+fn serialization_output_encoding() {
+  let parser = Parser::default();
+  let doc = parser.parse_string("<p>café</p>").unwrap();
+
+  let latin1 = OutputOptions {
+    encoding: Some("ISO-8859-1"),
+    ..OutputOptions::default()
+  };
+  assert_eq!(
+    doc.to_bytes_with_options(&latin1).unwrap(),
+    b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n<p>caf\xe9</p>\n"
+  );
+  let p = doc.get_root_readonly().unwrap();
+  assert_eq!(
+    doc.ronode_to_bytes_with_options(&p, &latin1).unwrap(),
+    b"<p>caf\xe9</p>"
+  );
+
+  let utf16 = OutputOptions {
+    encoding: Some("UTF-16"),
+    save: SaveOptions {
+      no_declaration: true,
+      ..SaveOptions::default()
+    },
+    ..OutputOptions::default()
+  };
+  let bytes = doc.to_bytes_with_options(&utf16).unwrap();
+  let units: Vec<u16> = bytes
+    .chunks(2)
+    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+    .collect();
+  assert_eq!(String::from_utf16(&units).unwrap(), "\u{feff}<p>café</p>\n");
+
+  let unknown = OutputOptions {
+    encoding: Some("NOT-AN-ENCODING"),
+    ..OutputOptions::default()
+  };
+  let error = doc.to_bytes_with_options(&unknown).unwrap_err();
+  assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+/// Creating and reading the `<!DOCTYPE ...>` declaration
+fn internal_subset() {
+  use libxml::tree::{Doctype, Document, Node};
+
+  let mut doc = Document::new().unwrap();
+  let root = Node::new("rss", None, &doc).unwrap();
+  doc.set_root_element(&root);
+  assert_eq!(doc.get_internal_subset(), None);
+  doc
+    .create_internal_subset(
+      "rss",
+      Some("-//Netscape Communications//DTD RSS 0.91//EN"),
+      Some("http://my.netscape.com/publish/formats/rss-0.91.dtd"),
+    )
+    .unwrap();
+  assert_eq!(
+    doc.to_string(),
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+     <!DOCTYPE rss PUBLIC \"-//Netscape Communications//DTD RSS 0.91//EN\" \
+     \"http://my.netscape.com/publish/formats/rss-0.91.dtd\">\n<rss/>\n"
+  );
+  assert!(doc.create_internal_subset("rss", None, None).is_err());
+
+  doc.remove_internal_subset();
+  doc.create_internal_subset("rss", None, None).unwrap();
+  assert_eq!(
+    doc.get_internal_subset(),
+    Some(Doctype {
+      name: "rss".to_string(),
+      public_id: None,
+      system_id: None,
+    })
+  );
+  assert_eq!(
+    doc.to_string(),
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE rss>\n<rss/>\n"
+  );
+
+  let parsed = Parser::default()
+    .parse_string("<!DOCTYPE note SYSTEM \"note.dtd\"><note/>")
+    .unwrap();
+  let doctype = parsed.get_internal_subset().unwrap();
+  assert_eq!(doctype.name, "note");
+  assert_eq!(doctype.public_id, None);
+  assert_eq!(doctype.system_id.as_deref(), Some("note.dtd"));
+}
+
+#[test]
+/// Version, declared encoding and standalone values of the XML declaration
+fn xml_declaration() {
+  let parser = Parser::default();
+  let mut doc = parser
+    .parse_string(
+      b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\" standalone=\"yes\"?><p>caf\xe9</p>",
+    )
+    .unwrap();
+  assert_eq!(doc.get_version().as_deref(), Some("1.0"));
+  assert_eq!(doc.get_encoding().as_deref(), Some("ISO-8859-1"));
+  assert_eq!(doc.get_standalone(), Some(true));
+
+  // serializers write UTF-8, unless asked for the declared encoding
+  let utf8 = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<p>café</p>\n";
+  assert_eq!(doc.to_string(), utf8);
+  let mut written = Vec::new();
+  doc.write_to(&mut written, SaveOptions::default()).unwrap();
+  assert_eq!(written, utf8.as_bytes());
+  assert_eq!(
+    doc.to_bytes_with_options(&OutputOptions::default()).unwrap(),
+    utf8.as_bytes()
+  );
+  let options = OutputOptions {
+    declared_encoding: true,
+    ..OutputOptions::default()
+  };
+  assert_eq!(
+    doc.to_bytes_with_options(&options).unwrap(),
+    b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\" standalone=\"yes\"?>\n<p>caf\xe9</p>\n"
+  );
+  // an explicit encoding wins
+  let explicit = OutputOptions {
+    encoding: Some("UTF-8"),
+    ..options
+  };
+  assert_eq!(doc.to_bytes_with_options(&explicit).unwrap(), utf8.as_bytes());
+
+  doc.set_version("1.1").unwrap();
+  doc.set_encoding(None).unwrap();
+  doc.set_standalone(Some(false));
+  assert_eq!(doc.get_encoding(), None);
+  assert_eq!(
+    doc.to_bytes_with_options(&options).unwrap(),
+    "<?xml version=\"1.1\" encoding=\"UTF-8\" standalone=\"no\"?>\n<p>café</p>\n".as_bytes()
+  );
+  doc.set_encoding(Some("UTF-16")).unwrap();
+  doc.set_standalone(None);
+  let utf16 = doc.to_bytes_with_options(&options).unwrap();
+  assert_eq!(&utf16[..2], b"\xff\xfe");
+  assert!(doc.set_version("1.\0").is_err());
+}
+
+#[test]
+/// `save_file` and `save_file_with_options` agree on the encoding of a
+/// document declared in ISO-8859-1
+fn save_file_encoding() {
+  let doc = Parser::default()
+    .parse_string(b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><p>caf\xe9</p>")
+    .unwrap();
+  let path = |name: &str| {
+    std::env::temp_dir()
+      .join(format!("rust_libxml_tests_{name}_{}.xml", std::process::id()))
+      .display()
+      .to_string()
+  };
+  let read = |path: &str| {
+    let bytes = std::fs::read(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    bytes
+  };
+
+  let plain = path("save_file");
+  doc.save_file(&plain).unwrap();
+  let with_options = path("save_file_with_options");
+  doc
+    .save_file_with_options(&with_options, &OutputOptions::default())
+    .unwrap();
+  let utf8 = read(&plain);
+  assert_eq!(utf8, read(&with_options));
+  assert_eq!(
+    utf8,
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<p>café</p>\n".as_bytes()
+  );
+
+  let declared = path("save_file_declared");
+  let options = OutputOptions {
+    declared_encoding: true,
+    ..OutputOptions::default()
+  };
+  doc.save_file_with_options(&declared, &options).unwrap();
+  assert_eq!(
+    read(&declared),
+    b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n<p>caf\xe9</p>\n"
+  );
+}
@@ -0,0 +1,299 @@
+//! Tree traversal iterator tests
+//!
+use libxml::parser::Parser;
+use libxml::readonly::RoNode;
+use libxml::tree::{Node, NodeType, TraversalEvent, WalkAction};
+
+const XML: &str = "<a><b><c/>text<d/></b><e/><!--note--><f><g/></f></a>";
+
+fn names(nodes: impl Iterator<Item = RoNode>) -> Vec<String> {
+  nodes.map(|node| node.get_name()).collect()
+}
+
+#[test]
+fn ronode_children_and_siblings() {
+  let doc = Parser::default().parse_string(XML).unwrap();
+  let a = doc.get_root_readonly().unwrap();
+
+  assert_eq!(names(a.children()), ["b", "e", "comment", "f"]);
+  assert_eq!(names(a.element_children()), ["b", "e", "f"]);
+  let b = a.get_first_child().unwrap();
+  assert_eq!(names(b.children()), ["c", "text", "d"]);
+  assert_eq!(names(b.element_children()), ["c", "d"]);
+
+  let e = b.get_next_sibling().unwrap();
+  assert_eq!(names(e.following_siblings()), ["comment", "f"]);
+  assert_eq!(names(e.preceding_siblings()), ["b"]);
+  assert_eq!(b.preceding_siblings().count(), 0);
+  assert_eq!(e.children().count(), 0);
+
+  let c = b.get_first_child().unwrap();
+  let ancestors: Vec<RoNode> = c.ancestors_iter().collect();
+  assert_eq!(ancestors.len(), 3);
+  assert_eq!(ancestors[0], b);
+  assert_eq!(ancestors[1], a);
+  assert!(ancestors[2].get_parent().is_none());
+}
+
+#[test]
+fn ronode_descendants() {
+  let doc = Parser::default().parse_string(XML).unwrap();
+  let a = doc.get_root_readonly().unwrap();
+
+  let descendants: Vec<(String, usize)> = a
+    .descendants()
+    .map(|(node, depth)| (node.get_name(), depth))
+    .collect();
+  let expected = [
+    ("b", 1),
+    ("c", 2),
+    ("text", 2),
+    ("d", 2),
+    ("e", 1),
+    ("comment", 1),
+    ("f", 1),
+    ("g", 2),
+  ];
+  assert_eq!(
+    descendants,
+    expected.map(|(name, depth)| (name.to_string(), depth))
+  );
+
+  // only the subtree of the starting node
+  let f = a.get_last_child().unwrap();
+  assert_eq!(names(f.descendants().map(|(node, _)| node)), ["g"]);
+  let g = f.get_first_child().unwrap();
+  assert_eq!(g.descendants().count(), 0);
+
+  // pruning
+  let mut pruned = Vec::new();
+  let mut descendants = a.descendants();
+  while let Some((node, _)) = descendants.next() {
+    if node.get_name() == "b" {
+      descendants.skip_children();
+    }
+    pruned.push(node.get_name());
+  }
+  assert_eq!(pruned, ["b", "e", "comment", "f", "g"]);
+}
+
+#[test]
+fn ronode_traverse() {
+  let doc = Parser::default()
+    .parse_string("<a><b><c/></b><d/></a>")
+    .unwrap();
+  let a = doc.get_root_readonly().unwrap();
+  let events: Vec<String> = a
+    .traverse()
+    .map(|event| match event {
+      TraversalEvent::Enter(node) => format!("+{}", node.get_name()),
+      TraversalEvent::Leave(node) => format!("-{}", node.get_name()),
+    })
+    .collect();
+  assert_eq!(events, ["+a", "+b", "+c", "-c", "-b", "+d", "-d", "-a"]);
+
+  let mut traverse = a.get_first_child().unwrap().traverse();
+  assert_eq!(traverse.next().map(|event| event.node().get_name()), Some("b".to_string()));
+  traverse.skip_children();
+  let rest: Vec<TraversalEvent<RoNode>> = traverse.collect();
+  assert_eq!(rest.len(), 1);
+  assert!(matches!(rest[0], TraversalEvent::Leave(node) if node.get_name() == "b"));
+}
+
+#[test]
+fn skip_children_after_leave() {
+  let doc = Parser::default()
+    .parse_string("<a><b><c/></b><d><e/></d><f/></a>")
+    .unwrap();
+  let a = doc.get_root_readonly().unwrap();
+  let mut traverse = a.traverse();
+  let mut events = Vec::new();
+  while let Some(event) = traverse.next() {
+    if matches!(event, TraversalEvent::Leave(_)) {
+      // nothing was just entered: the siblings are still visited
+      traverse.skip_children();
+    }
+    events.push(match event {
+      TraversalEvent::Enter(node) => format!("+{}", node.get_name()),
+      TraversalEvent::Leave(node) => format!("-{}", node.get_name()),
+    });
+  }
+  assert_eq!(
+    events,
+    ["+a", "+b", "+c", "-c", "-b", "+d", "+e", "-e", "-d", "+f", "-f", "-a"]
+  );
+
+  // a second call after the children were skipped does nothing either
+  let mut traverse = a.traverse();
+  traverse.next();
+  traverse.next();
+  traverse.skip_children();
+  traverse.skip_children();
+  let rest: Vec<String> = traverse.map(|event| event.node().get_name()).collect();
+  assert_eq!(rest, ["b", "d", "e", "e", "d", "f", "f", "a"]);
+
+  // descendants: skipping before the first one, or after a leaf, keeps the depths
+  let mut descendants = a.descendants();
+  descendants.skip_children();
+  let mut visited = Vec::new();
+  while let Some((node, depth)) = descendants.next() {
+    if node.get_name() == "c" {
+      descendants.skip_children();
+    }
+    visited.push((node.get_name(), depth));
+  }
+  let expected = [("b", 1), ("c", 2), ("d", 1), ("e", 2), ("f", 1)];
+  assert_eq!(visited, expected.map(|(name, depth)| (name.to_string(), depth)));
+}
+
+#[test]
+fn node_iterators() {
+  let doc = Parser::default().parse_string(XML).unwrap();
+  let a = doc.get_root_element().unwrap();
+
+  let children: Vec<Node> = a.children().collect();
+  assert_eq!(children, a.get_child_nodes());
+  let elements: Vec<Node> = a.element_children().collect();
+  assert_eq!(elements, a.get_child_elements());
+
+  let b = a.get_first_child().unwrap();
+  let d = b.get_last_child().unwrap();
+  assert_eq!(d.ancestors_iter().collect::<Vec<Node>>(), d.ancestors());
+  assert_eq!(
+    d.preceding_siblings()
+      .map(|node| node.get_name())
+      .collect::<Vec<_>>(),
+    ["text", "c"]
+  );
+  assert_eq!(b.following_siblings().count(), 3);
+
+  let depths: Vec<usize> = a.descendants().map(|(_, depth)| depth).collect();
+  assert_eq!(depths, [1, 2, 2, 2, 1, 1, 1, 2]);
+  let entered = a
+    .traverse()
+    .filter(|event| matches!(event, TraversalEvent::Enter(_)))
+    .count();
+  assert_eq!(entered, 9);
+
+  // the next child is looked up before the current one is returned
+  for mut child in a.children() {
+    if child.get_name() != "f" {
+      child.unlink();
+    }
+  }
+  assert_eq!(doc.node_to_string(&a), "<a><f><g/></f></a>");
+}
+
+#[test]
+/// Normalization pass: drop comments, skip `pre`, rename `b` to `strong`
+fn walk_with_mutation() {
+  let doc = Parser::default()
+    .parse_string(
+      "<doc><p>a<!--x--><b>bold<!--y--></b></p><pre><!--kept--><b>raw</b></pre><!--z--><p/></doc>",
+    )
+    .unwrap();
+  let root = doc.get_root_element().unwrap();
+
+  let mut visited = Vec::new();
+  root
+    .walk(|node, depth| {
+      visited.push((node.get_name(), depth));
+      match node.get_type() {
+        Some(NodeType::CommentNode) => WalkAction::Remove,
+        Some(NodeType::ElementNode) if node.get_name() == "pre" => WalkAction::SkipChildren,
+        Some(NodeType::ElementNode) if node.get_name() == "b" => {
+          let mut strong = Node::new("strong", None, &doc).unwrap();
+          for mut child in node.children() {
+            child.unlink();
+            strong.add_child(&mut child).unwrap();
+          }
+          WalkAction::Replace(strong)
+        }
+        _ => WalkAction::Continue,
+      }
+    })
+    .unwrap();
+
+  assert_eq!(
+    doc.node_to_string(&root),
+    "<doc><p>a<strong>bold<!--y--></strong></p><pre><!--kept--><b>raw</b></pre><p/></doc>"
+  );
+  let expected = [
+    ("p", 1),
+    ("text", 2),
+    ("comment", 2),
+    ("b", 2),
+    ("pre", 1),
+    ("comment", 1),
+    ("p", 1),
+  ];
+  assert_eq!(visited, expected.map(|(name, depth)| (name.to_string(), depth)));
+}
+
+#[test]
+fn walk_visits_added_children_and_stops() {
+  let doc = Parser::default()
+    .parse_string("<list><item/><item/><item/></list>")
+    .unwrap();
+  let root = doc.get_root_element().unwrap();
+
+  let mut count = 0;
+  root
+    .walk(|node, depth| {
+      if node.get_name() == "item" && depth == 1 && node.get_first_child().is_none() {
+        node.new_child(None, "label").unwrap();
+      }
+      count += 1;
+      if count == 4 {
+        WalkAction::Stop
+      } else {
+        WalkAction::Continue
+      }
+    })
+    .unwrap();
+  // item, its new label, the second item and its new label
+  assert_eq!(
+    doc.node_to_string(&root),
+    "<list><item><label/></item><item><label/></item><item/></list>"
+  );
+
+  // a node can not be replaced with its ancestor
+  let result = root.walk(|_, _| WalkAction::Replace(root.clone()));
+  assert!(result.is_err());
+}
+
+#[test]
+/// A fragment replacement puts its children in the tree, not the fragment
+fn walk_replace_with_fragment() {
+  let doc = Parser::default()
+    .parse_string("<p>a<br/>b<em/></p>")
+    .unwrap();
+  let root = doc.get_root_element().unwrap();
+
+  let mut visited = Vec::new();
+  root
+    .walk(|node, _| {
+      visited.push(node.get_name());
+      if node.get_name() != "br" {
+        return WalkAction::Continue;
+      }
+      let mut fragment = Node::new_fragment(&doc).unwrap();
+      let mut before = Node::new_text("-", &doc).unwrap();
+      let mut hr = Node::new("hr", None, &doc).unwrap();
+      let mut after = Node::new_text("-", &doc).unwrap();
+      fragment.add_child(&mut before).unwrap();
+      fragment.add_child(&mut hr).unwrap();
+      fragment.add_child(&mut after).unwrap();
+      WalkAction::Replace(fragment)
+    })
+    .unwrap();
+
+  assert_eq!(doc.node_to_string(&root), "<p>a-<hr/>-b<em/></p>");
+  assert_eq!(visited, ["text", "br", "text", "em"]);
+  assert!(
+    root
+      .get_child_nodes()
+      .iter()
+      .all(|child| child.get_type() != Some(NodeType::DocumentFragNode))
+  );
+}
@@ -667,3 +667,465 @@ fn can_replace_child() {
     "document initialized correctly."
   );
 }
+
+#[test]
+/// Shallow and deep copies within a document
+fn can_copy_node() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_string("<root><item id=\"1\" class=\"a\"><name>first</name></item></root>")
+    .unwrap();
+  let mut root = doc.get_root_element().unwrap();
+  let item = root.get_first_element_child().unwrap();
+
+  let mut deep = item.copy(true).unwrap();
+  assert!(deep.is_unlinked());
+  assert!(deep.get_parent().is_none());
+  assert_eq!(
+    doc.node_to_string(&deep),
+    "<item id=\"1\" class=\"a\"><name>first</name></item>"
+  );
+  let mut shallow = item.copy(false).unwrap();
+  assert_eq!(doc.node_to_string(&shallow), "<item id=\"1\" class=\"a\"/>");
+
+  // copies are independent of the original
+  shallow.set_attribute("id", "2").unwrap();
+  assert_eq!(item.get_attribute("id"), Some("1".to_string()));
+
+  root.add_child(&mut deep).unwrap();
+  root.add_child(&mut shallow).unwrap();
+  assert!(!deep.is_unlinked());
+  assert_eq!(
+    doc.to_string(),
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<root><item id=\"1\" class=\"a\"><name>first</name></item>\
+<item id=\"1\" class=\"a\"><name>first</name></item><item id=\"2\" class=\"a\"/></root>\n"
+  );
+
+  // document nodes are not copied as nodes
+  let doc_node = root.get_parent().unwrap();
+  assert!(doc_node.copy(true).is_err());
+}
+
+#[test]
+/// Copies into another document carry the namespaces in scope
+fn can_copy_node_into_document() {
+  let parser = Parser::default();
+  let source = parser
+    .parse_string("<r:root xmlns:r=\"urn:r\"><r:item>text</r:item></r:root>")
+    .unwrap();
+  let item = source.get_root_element().unwrap().get_first_element_child().unwrap();
+
+  let mut target = Document::new().unwrap();
+  let mut copy = item.copy_into(&target, true).unwrap();
+  assert!(copy.is_unlinked());
+  target.set_root_element(&copy);
+  drop(source);
+  assert_eq!(
+    target.to_string(),
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<r:item xmlns:r=\"urn:r\">text</r:item>\n"
+  );
+
+  // a copy made outside of any tree declares the namespaces it uses itself
+  let mut child = copy.copy_into(&target, false).unwrap();
+  copy.add_child(&mut child).unwrap();
+  assert_eq!(
+    target.node_to_string(&copy),
+    "<r:item xmlns:r=\"urn:r\">text<r:item xmlns:r=\"urn:r\"/></r:item>"
+  );
+}
+
+#[test]
+/// Copies that are never attached are freed with their document
+fn unattached_copies_are_freed() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_string("<root><item><name>first</name></item></root>")
+    .unwrap();
+  let item = doc.get_root_element().unwrap().get_first_element_child().unwrap();
+  let copy = item.copy(true).unwrap();
+  // wrappers of the copy's descendants are released along with it
+  let name = copy.get_first_child().unwrap();
+  assert_eq!(name.get_content(), "first");
+  let other = Document::new().unwrap();
+  let _foreign = item.copy_into(&other, true).unwrap();
+  drop(copy);
+  drop(name);
+  drop(doc);
+  drop(other);
+}
+
+#[test]
+/// Source line numbers and paths of parsed and created nodes
+fn line_numbers_and_paths() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_string("<root>\n  <item/>\n  <item id=\"2\">\n    text\n  </item>\n</root>")
+    .unwrap();
+  let root = doc.get_root_element().unwrap();
+  let items = root.get_child_elements();
+  assert_eq!(root.get_line_number(), Some(1));
+  assert_eq!(items[1].get_line_number(), Some(3));
+  assert_eq!(items[1].get_path().unwrap(), "/root/item[2]");
+  let id = items[1].get_attribute_node("id").unwrap();
+  assert_eq!(id.get_path().unwrap(), "/root/item[2]/@id");
+  let text = items[1].get_first_child().unwrap();
+  assert_eq!(text.get_path().unwrap(), "/root/item[2]/text()");
+
+  let ro_item = doc.get_root_readonly().unwrap().get_child_elements()[0];
+  assert_eq!(ro_item.get_line_number(), Some(2));
+  assert_eq!(ro_item.get_path().unwrap(), "/root/item[1]");
+
+  let created = Node::new("created", None, &doc).unwrap();
+  assert_eq!(created.get_line_number(), None);
+  assert_eq!(created.get_path().unwrap(), "/created");
+}
+
+#[test]
+/// Line numbers above 65535 need the big lines parser option
+fn big_line_numbers() {
+  use libxml::parser::ParserOptions;
+
+  let input = format!("<root>{}<last>text</last></root>", "\n".repeat(70_000));
+  let parser = Parser::default();
+  let text_line = |options: ParserOptions| {
+    let doc = parser.parse_string_with_options(&input, options).unwrap();
+    let root = doc.get_root_readonly().unwrap();
+    let last = root.get_first_element_child().unwrap();
+    last.get_first_child().unwrap().get_line_number()
+  };
+  assert_eq!(text_line(ParserOptions::default()), Some(65_535));
+  assert_eq!(
+    text_line(ParserOptions {
+      big_lines: true,
+      ..ParserOptions::default()
+    }),
+    Some(70_001)
+  );
+}
+
+#[test]
+/// CDATA sections and entity references are serialized as created
+fn cdata_and_entity_ref_nodes() {
+  let doc = Parser::default()
+    .parse_string("<!DOCTYPE item [<!ENTITY brand \"Acme\">]><item/>")
+    .unwrap();
+  let mut item = doc.get_root_element().unwrap();
+  let mut cdata = Node::new_cdata("<p>Fish & chips</p>", &doc).unwrap();
+  assert_eq!(cdata.get_type(), Some(NodeType::CDataSectionNode));
+  assert_eq!(cdata.get_content(), "<p>Fish & chips</p>");
+  item.add_child(&mut cdata).unwrap();
+
+  let mut declared = Node::new_entity_ref("&brand;", &doc).unwrap();
+  assert_eq!(declared.get_type(), Some(NodeType::EntityRefNode));
+  assert_eq!(declared.get_name(), "brand");
+  item.add_child(&mut declared).unwrap();
+  let mut undeclared = Node::new_entity_ref("nbsp", &doc).unwrap();
+  item.add_child(&mut undeclared).unwrap();
+
+  assert_eq!(
+    doc.node_to_string(&item),
+    "<item><![CDATA[<p>Fish & chips</p>]]>&brand;&nbsp;</item>"
+  );
+  assert!(Node::new_cdata("nul\0", &doc).is_err());
+}
+
+#[test]
+/// The children of a fragment are moved where the fragment is inserted
+fn insert_document_fragment() {
+  let doc = Parser::default()
+    .parse_string("<list>start<item>b</item>end</list>")
+    .unwrap();
+  let mut list = doc.get_root_element().unwrap();
+
+  let build = |texts: &[&str]| {
+    let mut fragment = Node::new_fragment(&doc).unwrap();
+    assert_eq!(fragment.get_type(), Some(NodeType::DocumentFragNode));
+    for text in texts {
+      let mut node = match text.strip_prefix('<') {
+        Some(name) => Node::new(name, None, &doc).unwrap(),
+        None => Node::new_text(text, &doc).unwrap(),
+      };
+      fragment.add_child(&mut node).unwrap();
+    }
+    fragment
+  };
+
+  // text nodes are merged with the text next to the insertion point
+  let mut item = list.get_first_element_child().unwrap();
+  item
+    .add_prev_sibling(&mut build(&["-", "<a", "-"]))
+    .unwrap();
+  item.add_next_sibling(&mut build(&["+", "<c"])).unwrap();
+  let mut appended = build(&["!", "<d"]);
+  list.add_child(&mut appended).unwrap();
+  assert_eq!(appended.get_first_child(), None);
+  // inserting an empty fragment is a no-op
+  list.add_child(&mut appended).unwrap();
+
+  assert_eq!(
+    doc.node_to_string(&list),
+    "<list>start-<a/>-<item>b</item>+<c/>end!<d/></list>"
+  );
+  let names: Vec<String> = list
+    .get_child_nodes()
+    .iter()
+    .map(|child| child.get_name())
+    .collect();
+  assert_eq!(names, ["text", "a", "text", "item", "text", "c", "text", "d"]);
+  for child in list.get_child_nodes() {
+    assert_eq!(child.get_parent().unwrap(), list);
+  }
+
+  // the order is kept around a following text node, whose wrapper stays valid
+  let mut end = list.get_child_nodes()[6].clone();
+  let mut fragment = build(&["x", "<i"]);
+  let mut y = Node::new_text("y", &doc).unwrap();
+  fragment.add_child(&mut y).unwrap();
+  end.add_prev_sibling(&mut fragment).unwrap();
+  assert_eq!(
+    doc.node_to_string(&list),
+    "<list>start-<a/>-<item>b</item>+<c/>x<i/>yend!<d/></list>"
+  );
+  assert_eq!(end.get_content(), "yend!");
+  // `y` was merged into `end` and freed
+  assert!(y.node_ptr().is_null());
+
+  // fragments only go into elements, documents and fragments
+  assert!(end.add_child(&mut build(&["<j"])).is_err());
+  let mut attribute = list.get_first_element_child().unwrap();
+  attribute.set_attribute("k", "v").unwrap();
+  let mut attribute = attribute.get_attribute_node("k").unwrap();
+  assert!(attribute.add_next_sibling(&mut build(&["<j"])).is_err());
+  assert!(attribute.add_child(&mut build(&["<j"])).is_err());
+
+  // a fragment can not be inserted into itself
+  let mut fragment = build(&["<e"]);
+  let mut inner = fragment.get_first_child().unwrap();
+  assert!(inner.add_next_sibling(&mut fragment).is_err());
+}
+
+#[test]
+/// Lookup of `xml:id` and DTD-declared IDs, following attribute changes
+fn get_element_by_id() {
+  let parser = Parser::default();
+  let doc = parser.parse_file("tests/resources/ids.xml").unwrap();
+  let mid = doc.get_element_by_id("mid").unwrap();
+  assert_eq!(mid.get_name(), "p");
+  assert_eq!(mid.get_parent().unwrap().get_name(), "deeper");
+  let end = doc.get_element_by_id_readonly("end").unwrap();
+  assert_eq!(end.get_content(), "World!");
+  assert!(doc.get_element_by_id("nowhere").is_none());
+
+  // setting, changing and removing IDs
+  let mut root = doc.get_root_element().unwrap();
+  let mut added = root.new_child(None, "added").unwrap();
+  added.set_attribute("xml:id", "new").unwrap();
+  assert_eq!(doc.get_element_by_id("new").unwrap(), added);
+  added.set_attribute("xml:id", "renamed").unwrap();
+  assert!(doc.get_element_by_id("new").is_none());
+  assert_eq!(doc.get_element_by_id("renamed").unwrap(), added);
+  added
+    .remove_attribute_ns("id", "http://www.w3.org/XML/1998/namespace")
+    .unwrap();
+  assert!(doc.get_element_by_id("renamed").is_none());
+
+  // unlinked elements are not found, and give their ID up
+  let mut start = doc.get_element_by_id("start").unwrap();
+  start.unlink();
+  assert!(doc.get_element_by_id("start").is_none());
+  added.set_attribute("xml:id", "start").unwrap();
+  assert_eq!(doc.get_element_by_id("start").unwrap(), added);
+  // the first element keeps a duplicated ID
+  let mut duplicate = root.new_child(None, "duplicate").unwrap();
+  duplicate.set_attribute("xml:id", "start").unwrap();
+  assert_eq!(doc.get_element_by_id("start").unwrap(), added);
+
+  // IDs declared by the DTD
+  let doc = parser
+    .parse_string(
+      "<!DOCTYPE items [<!ATTLIST item key ID #IMPLIED>]>\
+       <items><item key='a'/><item id='b'/></items>",
+    )
+    .unwrap();
+  assert_eq!(
+    doc.get_element_by_id("a").unwrap().get_attribute("key"),
+    Some("a".to_string())
+  );
+  assert!(doc.get_element_by_id("b").is_none());
+  let mut items = doc.get_root_element().unwrap();
+  let mut item = items.new_child(None, "item").unwrap();
+  item.set_attribute("key", "c").unwrap();
+  assert_eq!(doc.get_element_by_id("c").unwrap(), item);
+}
+
+#[test]
+/// Declaring the namespaces of a subtree moved out of their scope
+fn reconcile_moved_namespaces() {
+  let doc = Parser::default()
+    .parse_string("<root><x:holder xmlns:x='urn:x'><x:item x:a='1'/></x:holder><target/></root>")
+    .unwrap();
+  let root = doc.get_root_element().unwrap();
+  let mut holder = root.get_first_element_child().unwrap();
+  let mut target = holder.get_next_element_sibling().unwrap();
+  let mut item = holder.get_first_element_child().unwrap();
+  item.unlink();
+  target.add_child(&mut item).unwrap();
+  holder.unlink();
+  assert_eq!(
+    doc.node_to_string(&target),
+    "<target><x:item x:a=\"1\"/></target>"
+  );
+
+  item.reconcile_namespaces().unwrap();
+  assert_eq!(
+    doc.node_to_string(&target),
+    "<target><x:item xmlns:x=\"urn:x\" x:a=\"1\"/></target>"
+  );
+  // nothing left to declare
+  item.reconcile_namespaces().unwrap();
+  assert_eq!(
+    doc.node_to_string(&target),
+    "<target><x:item xmlns:x=\"urn:x\" x:a=\"1\"/></target>"
+  );
+}
+
+#[test]
+/// Adopting a subtree into another document, then removing the
+/// declarations made redundant by its new ancestors
+fn adopt_node_between_documents() {
+  use libxml::tree::ReconcileOptions;
+
+  let parser = Parser::default();
+  let source = parser
+    .parse_string(
+      "<a:root xmlns:a='urn:a'><a:item xml:id='i1' a:attr='v'><a:child/></a:item></a:root>",
+    )
+    .unwrap();
+  let mut target = parser
+    .parse_string("<root xmlns:a='urn:a'><a:first/></root>")
+    .unwrap();
+  let mut item = source
+    .get_root_element()
+    .unwrap()
+    .get_first_element_child()
+    .unwrap();
+  let child = item.get_first_element_child().unwrap();
+
+  target.adopt_node(&mut item).unwrap();
+  assert!(item.is_unlinked());
+  assert!(source.get_element_by_id("i1").is_none());
+  assert_eq!(
+    source.to_string(),
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<a:root xmlns:a=\"urn:a\"/>\n"
+  );
+  drop(source);
+
+  let mut root = target.get_root_element().unwrap();
+  root.add_child(&mut item).unwrap();
+  assert_eq!(child.get_parent().unwrap(), item);
+  assert_eq!(target.get_element_by_id("i1").unwrap(), item);
+  assert_eq!(
+    target.node_to_string(&root),
+    "<root xmlns:a=\"urn:a\"><a:first/><a:item xmlns:a=\"urn:a\" xml:id=\"i1\" a:attr=\"v\">\
+     <a:child/></a:item></root>"
+  );
+
+  let options = ReconcileOptions {
+    remove_redundant: true,
+  };
+  item.reconcile_namespaces_with(options).unwrap();
+  assert_eq!(
+    target.node_to_string(&root),
+    "<root xmlns:a=\"urn:a\"><a:first/><a:item xml:id=\"i1\" a:attr=\"v\"><a:child/></a:item></root>"
+  );
+  assert_eq!(child.get_namespace().unwrap().get_href(), "urn:a");
+
+  // document nodes can not be adopted
+  let other = parser.parse_string("<other/>").unwrap();
+  assert!(target.adopt_node(&mut other.as_node()).is_err());
+}
+
+#[test]
+/// Elements created in a namespace use the declaration in scope once attached
+fn new_element_in_namespace() {
+  let doc = Parser::default()
+    .parse_string("<feed xmlns='http://www.w3.org/2005/Atom' xmlns:m='urn:media'/>")
+    .unwrap();
+  let mut feed = doc.get_root_element().unwrap();
+
+  // detached, the element declares its namespace itself
+  let mut entry = Node::new_ns("entry", "http://www.w3.org/2005/Atom", Some("atom"), &doc).unwrap();
+  assert_eq!(
+    doc.node_to_string(&entry),
+    "<atom:entry xmlns:atom=\"http://www.w3.org/2005/Atom\"/>"
+  );
+  let mut thumbnail = Node::new_ns("thumbnail", "urn:media", None, &doc).unwrap();
+  let mut extension = Node::new_ns("ext", "urn:ext", Some("x"), &doc).unwrap();
+  let plain = Node::new_ns("plain", "", None, &doc).unwrap();
+  assert!(plain.get_namespace().is_none());
+
+  // attached, the declarations in scope are used, whatever their prefix
+  feed.add_child(&mut entry).unwrap();
+  entry.add_child(&mut thumbnail).unwrap();
+  entry.add_child(&mut extension).unwrap();
+  assert_eq!(
+    doc.node_to_string(&feed),
+    "<feed xmlns=\"http://www.w3.org/2005/Atom\" xmlns:m=\"urn:media\">\
+     <entry><m:thumbnail/><x:ext xmlns:x=\"urn:ext\"/></entry></feed>"
+  );
+  assert_eq!(
+    entry.get_namespace().unwrap().get_href(),
+    "http://www.w3.org/2005/Atom"
+  );
+  assert_eq!(thumbnail.get_namespace().unwrap().get_prefix(), "m");
+
+  // attributes in the namespace keep a prefixed declaration
+  let mut link = Node::new_ns("link", "http://www.w3.org/2005/Atom", Some("atom"), &doc).unwrap();
+  link
+    .set_attribute_ns_uri("http://www.w3.org/2005/Atom", "rel", "self")
+    .unwrap();
+  entry.add_child(&mut link).unwrap();
+  assert_eq!(
+    doc.node_to_string(&link),
+    "<atom:link xmlns:atom=\"http://www.w3.org/2005/Atom\" atom:rel=\"self\"/>"
+  );
+}
+
+#[test]
+/// Attributes set by namespace URI reuse a prefix in scope, or declare one
+fn set_attribute_by_namespace_uri() {
+  let doc = Parser::default()
+    .parse_string("<root xmlns='urn:default' xmlns:m='urn:media'><item/></root>")
+    .unwrap();
+  let mut item = doc
+    .get_root_element()
+    .unwrap()
+    .get_first_element_child()
+    .unwrap();
+  item
+    .set_attribute_ns_uri("urn:media", "width", "10")
+    .unwrap();
+  // attributes have no default namespace
+  item
+    .set_attribute_ns_uri("urn:default", "kind", "a")
+    .unwrap();
+  item.set_attribute_ns_uri("urn:other", "note", "b").unwrap();
+  item.set_attribute_ns_uri("urn:other", "note", "c").unwrap();
+  item.set_attribute_ns_uri("", "plain", "d").unwrap();
+  item
+    .set_attribute_ns_uri("http://www.w3.org/XML/1998/namespace", "lang", "en")
+    .unwrap();
+  assert_eq!(
+    doc.node_to_string(&item),
+    "<item xmlns:ns0=\"urn:default\" xmlns:ns1=\"urn:other\" m:width=\"10\" ns0:kind=\"a\" \
+     ns1:note=\"c\" plain=\"d\" xml:lang=\"en\"/>"
+  );
+  assert_eq!(
+    item.get_property_ns("note", "urn:other"),
+    Some("c".to_string())
+  );
+  assert_eq!(
+    item.get_property_ns("kind", "urn:default"),
+    Some("a".to_string())
+  );
+}
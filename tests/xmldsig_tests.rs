@@ -0,0 +1,361 @@
+//! XML-DSig tests, against samples signed with xmllint and openssl
+//!
+#![cfg(feature = "xmldsig")]
+
+use std::cell::RefCell;
+
+use rsa::pkcs1v15::{Signature as RsaSignature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use sha2::{Digest, Sha256};
+
+use libxml::parser::Parser;
+use libxml::tree::c14n::{CanonicalizationMode, CanonicalizationOptions};
+use libxml::tree::Node;
+use libxml::xmldsig::{
+  find_signatures, DigestAlgorithm, Reference, Signature, SignatureVerifier, Signer, Transform,
+  XmlDsigError,
+};
+
+const HMAC_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#hmac-sha256";
+const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const HMAC_KEY: &[u8] = b"correct horse battery staple";
+
+/// HMAC-SHA256 (RFC 2104) signer and verifier
+struct Hmac(&'static [u8]);
+
+impl Hmac {
+  fn mac(&self, data: &[u8]) -> Vec<u8> {
+    let mut key = [0u8; 64];
+    key[..self.0.len()].copy_from_slice(self.0);
+    let pad = |byte: u8| key.iter().map(|k| k ^ byte).collect::<Vec<u8>>();
+    let inner = Sha256::new()
+      .chain_update(pad(0x36))
+      .chain_update(data)
+      .finalize();
+    Sha256::new()
+      .chain_update(pad(0x5c))
+      .chain_update(inner)
+      .finalize()
+      .to_vec()
+  }
+}
+
+impl SignatureVerifier for Hmac {
+  fn verify(&self, algorithm: &str, data: &[u8], signature: &[u8]) -> bool {
+    algorithm == HMAC_SHA256 && self.mac(data) == signature
+  }
+}
+
+impl Signer for Hmac {
+  fn sign(&self, algorithm: &str, data: &[u8]) -> Result<Vec<u8>, ()> {
+    if algorithm == HMAC_SHA256 {
+      Ok(self.mac(data))
+    } else {
+      Err(())
+    }
+  }
+}
+
+/// RSA-SHA256 (PKCS#1 v1.5) verifier
+struct Rsa(VerifyingKey<Sha256>);
+
+impl Rsa {
+  fn from_pem_file(path: &str) -> Self {
+    let pem = std::fs::read_to_string(path).unwrap();
+    Rsa(VerifyingKey::new(RsaPublicKey::from_public_key_pem(&pem).unwrap()))
+  }
+}
+
+impl SignatureVerifier for Rsa {
+  fn verify(&self, algorithm: &str, data: &[u8], signature: &[u8]) -> bool {
+    algorithm == RSA_SHA256
+      && RsaSignature::try_from(signature).is_ok_and(|value| self.0.verify(data, &value).is_ok())
+  }
+}
+
+/// `(algorithm, data, signature)` passed to a verifier
+type Verification = (String, Vec<u8>, Vec<u8>);
+
+/// Accepts any signature, recording what it was asked to verify
+#[derive(Default)]
+struct Recorder(RefCell<Vec<Verification>>);
+
+impl SignatureVerifier for Recorder {
+  fn verify(&self, algorithm: &str, data: &[u8], signature: &[u8]) -> bool {
+    self
+      .0
+      .borrow_mut()
+      .push((algorithm.to_owned(), data.to_vec(), signature.to_vec()));
+    true
+  }
+}
+
+#[test]
+/// The reference digest is the digest of the canonical form of the fixtures
+fn reference_digest_of_canonical_fixtures() {
+  let parser = Parser::default();
+  let input = include_str!("resources/canonical_exclusive/2_input.xml");
+  let expected = include_str!("resources/canonical_exclusive/2_output.xml");
+  let doc = parser.parse_string(input).unwrap();
+
+  let reference = Reference {
+    uri: Some(String::new()),
+    transforms: vec![Transform::Canonicalization(CanonicalizationOptions {
+      mode: CanonicalizationMode::ExclusiveCanonical1_0,
+      with_comments: false,
+      inclusive_ns_prefixes: vec!["stay1".to_string(), "stay2".to_string()],
+    })],
+    digest_method: DigestAlgorithm::Sha256,
+    digest_value: Vec::new(),
+  };
+  // the fixture has a trailing newline, see tests/c14n.rs
+  assert_eq!(
+    reference.compute_digest(&doc, None).unwrap(),
+    DigestAlgorithm::Sha256.digest(expected.trim_end().as_bytes())
+  );
+
+  // the enveloped-signature transform needs the enclosing signature
+  let enveloped = Reference {
+    transforms: vec![Transform::EnvelopedSignature],
+    ..reference
+  };
+  assert_eq!(
+    enveloped.compute_digest(&doc, None),
+    Err(XmlDsigError::Missing("Signature"))
+  );
+}
+
+#[test]
+fn parse_signature() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_file("tests/resources/xmldsig/enveloped_hmac.xml")
+    .unwrap();
+  let nodes = find_signatures(&doc);
+  assert_eq!(nodes.len(), 1);
+  let signature = Signature::parse(nodes[0]).unwrap();
+  assert_eq!(signature.node(), nodes[0]);
+
+  let signed_info = &signature.signed_info;
+  assert_eq!(
+    signed_info.canonicalization.mode,
+    CanonicalizationMode::ExclusiveCanonical1_0
+  );
+  assert_eq!(signed_info.signature_method, HMAC_SHA256);
+  assert_eq!(signed_info.references.len(), 2);
+  let whole = &signed_info.references[0];
+  assert_eq!(whole.uri.as_deref(), Some(""));
+  assert_eq!(whole.transforms[0], Transform::EnvelopedSignature);
+  assert_eq!(whole.digest_method, DigestAlgorithm::Sha256);
+  assert_eq!(whole.digest_value.len(), 32);
+  let note = &signed_info.references[1];
+  assert!(note.transforms.is_empty());
+  assert_eq!(note.digest_method, DigestAlgorithm::Sha512);
+  assert_eq!(note.resolve(&doc).unwrap().get_name(), "note");
+  assert_eq!(signature.signature_value.len(), 32);
+
+  let root = doc.get_root_readonly().unwrap();
+  assert_eq!(
+    Signature::parse(root).unwrap_err(),
+    XmlDsigError::Missing("Signature")
+  );
+}
+
+#[test]
+/// An enveloped HMAC signature over the whole document and an element
+fn verify_enveloped_signature() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_file("tests/resources/xmldsig/enveloped_hmac.xml")
+    .unwrap();
+  let signature = Signature::parse(find_signatures(&doc)[0]).unwrap();
+  assert_eq!(signature.verify(&doc, &Hmac(HMAC_KEY)), Ok(()));
+  assert_eq!(
+    signature.verify(&doc, &Hmac(b"wrong key")),
+    Err(XmlDsigError::InvalidSignature)
+  );
+
+  // tampering with the signed content is detected by the reference digests
+  let mut note = doc
+    .get_root_element()
+    .unwrap()
+    .get_child_elements()
+    .into_iter()
+    .find(|element| element.get_name() == "note")
+    .unwrap();
+  note.set_content("Leave with the neighbours").unwrap();
+  assert_eq!(
+    signature.verify(&doc, &Hmac(HMAC_KEY)),
+    Err(XmlDsigError::DigestMismatch(String::new()))
+  );
+}
+
+#[test]
+/// SAML-style signature of the element carrying `ID`, with comments stripped
+fn verify_id_reference() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_file("tests/resources/xmldsig/saml_response.xml")
+    .unwrap();
+  let signature = Signature::parse(find_signatures(&doc)[0]).unwrap();
+  let reference = &signature.signed_info.references[0];
+  assert_eq!(reference.uri.as_deref(), Some("#_resp1"));
+  assert_eq!(reference.digest_method, DigestAlgorithm::Sha1);
+  assert_eq!(
+    reference.resolve(&doc).unwrap(),
+    doc.get_root_readonly().unwrap()
+  );
+
+  let key = Rsa::from_pem_file("tests/resources/xmldsig/saml_response_key.pem");
+  assert_eq!(signature.verify(&doc, &key), Ok(()));
+  assert_eq!(
+    signature.verify(&doc, &Hmac(HMAC_KEY)),
+    Err(XmlDsigError::InvalidSignature)
+  );
+
+  let recorder = Recorder::default();
+  assert_eq!(signature.verify(&doc, &recorder), Ok(()));
+  let calls = recorder.0.into_inner();
+  assert_eq!(calls.len(), 1);
+  let (algorithm, data, value) = &calls[0];
+  assert_eq!(algorithm, RSA_SHA256);
+  assert_eq!(
+    data,
+    &std::fs::read("tests/resources/xmldsig/saml_response_signed_info.xml").unwrap()
+  );
+  assert_eq!(value.len(), 256);
+
+  // comments are not part of the referenced data
+  let mut root = doc.get_root_element().unwrap();
+  let mut comment = Node::new_comment(" audit ", &doc).unwrap();
+  root.add_child(&mut comment).unwrap();
+  assert_eq!(signature.verify(&doc, &key), Ok(()));
+
+  // a valid SignatureValue does not cover tampered content
+  let mut issuer = root.findnodes("//*[local-name()='Issuer']").unwrap()[0].clone();
+  issuer.set_content("https://attacker.example.com").unwrap();
+  assert_eq!(
+    signature.verify(&doc, &key),
+    Err(XmlDsigError::DigestMismatch("#_resp1".to_string()))
+  );
+}
+
+#[test]
+/// Transforms are applied in order: enveloped-signature, then canonicalization
+fn reject_unsupported_transform_chains() {
+  let parser = Parser::default();
+  let source = std::fs::read_to_string("tests/resources/xmldsig/enveloped_hmac.xml").unwrap();
+  let enveloped = r#"<ds:Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"/>"#;
+  assert!(source.contains(enveloped));
+
+  let twice = source.replacen(enveloped, &format!("{enveloped}{enveloped}"), 1);
+  let doc = parser.parse_string(&twice).unwrap();
+  assert_eq!(
+    Signature::parse(find_signatures(&doc)[0]).unwrap_err(),
+    XmlDsigError::UnsupportedTransforms
+  );
+
+  // the canonical octets have no signature left to remove
+  let doc = parser.parse_string(&source).unwrap();
+  let signature = Signature::parse(find_signatures(&doc)[0]).unwrap();
+  let mut reversed = signature.signed_info.references[0].clone();
+  reversed.transforms.reverse();
+  assert_eq!(
+    reversed.compute_digest(&doc, Some(signature.node())),
+    Err(XmlDsigError::UnsupportedTransforms)
+  );
+}
+
+#[test]
+/// A reference without `URI` is resolved by the caller only
+fn reference_without_uri() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_file("tests/resources/xmldsig/enveloped_hmac.xml")
+    .unwrap();
+  let signature = Signature::parse(find_signatures(&doc)[0]).unwrap();
+  let whole = &signature.signed_info.references[0];
+  let implicit = Reference {
+    uri: None,
+    ..whole.clone()
+  };
+  assert_eq!(implicit.resolve(&doc), Err(XmlDsigError::Missing("URI")));
+  assert_eq!(
+    implicit.compute_digest(&doc, Some(signature.node())),
+    Err(XmlDsigError::Missing("URI"))
+  );
+  let document_node = doc.get_root_readonly().unwrap().get_parent().unwrap();
+  assert_eq!(
+    implicit.compute_digest_of(&doc, document_node, Some(signature.node())),
+    Ok(whole.digest_value.clone())
+  );
+
+  let source = std::fs::read_to_string("tests/resources/xmldsig/enveloped_hmac.xml").unwrap();
+  let without_uri = source.replacen(" URI=\"\"", "", 1);
+  let doc = parser.parse_string(&without_uri).unwrap();
+  let signature = Signature::parse(find_signatures(&doc)[0]).unwrap();
+  assert_eq!(
+    signature.verify(&doc, &Recorder::default()),
+    Err(XmlDsigError::Missing("URI"))
+  );
+}
+
+#[test]
+/// A wrapped copy of the signed element makes the reference ambiguous
+fn reject_duplicate_ids() {
+  let parser = Parser::default();
+  let source = std::fs::read_to_string("tests/resources/xmldsig/saml_response.xml").unwrap();
+  let wrapped = source.replace(
+    "<saml:Assertion ID=\"_a1\"",
+    "<samlp:Extensions ID=\"_resp1\"/>\n  <saml:Assertion ID=\"_a1\"",
+  );
+  let doc = parser.parse_string(&wrapped).unwrap();
+  let signature = Signature::parse(find_signatures(&doc)[0]).unwrap();
+  assert_eq!(
+    signature.verify(&doc, &Recorder::default()),
+    Err(XmlDsigError::AmbiguousReference("#_resp1".to_string()))
+  );
+
+  let missing = source.replace("URI=\"#_resp1\"", "URI=\"#nowhere\"");
+  let doc = parser.parse_string(&missing).unwrap();
+  let signature = Signature::parse(find_signatures(&doc)[0]).unwrap();
+  assert_eq!(
+    signature.verify(&doc, &Recorder::default()),
+    Err(XmlDsigError::UnresolvedReference("#nowhere".to_string()))
+  );
+}
+
+#[test]
+fn unsupported_algorithms() {
+  let parser = Parser::default();
+  let source = std::fs::read_to_string("tests/resources/xmldsig/enveloped_hmac.xml").unwrap();
+  let md5 = source.replace(
+    "http://www.w3.org/2001/04/xmlenc#sha512",
+    "http://www.w3.org/2001/04/xmldsig-more#md5",
+  );
+  let doc = parser.parse_string(&md5).unwrap();
+  assert_eq!(
+    Signature::parse(find_signatures(&doc)[0]).unwrap_err(),
+    XmlDsigError::UnsupportedAlgorithm("http://www.w3.org/2001/04/xmldsig-more#md5".to_string())
+  );
+}
+
+#[test]
+/// Signing a template produces the values of the independently signed sample
+fn sign_template() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_file("tests/resources/xmldsig/enveloped_hmac_template.xml")
+    .unwrap();
+  let mut signature = Signature::parse(find_signatures(&doc)[0]).unwrap();
+  assert!(signature.signature_value.is_empty());
+  signature.sign(&doc, &Hmac(HMAC_KEY)).unwrap();
+  assert_eq!(signature.verify(&doc, &Hmac(HMAC_KEY)), Ok(()));
+
+  let signed = parser
+    .parse_file("tests/resources/xmldsig/enveloped_hmac.xml")
+    .unwrap();
+  assert_eq!(doc.to_string(), signed.to_string());
+}
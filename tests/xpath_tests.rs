@@ -2,7 +2,7 @@
 //!
 
 use libxml::parser::Parser;
-use libxml::xpath::Context;
+use libxml::xpath::{Context, NamespaceConflict, NamespaceRegistration, XPointerError};
 
 #[test]
 /// Test the evaluation of an xpath expression yields the correct number of nodes
@@ -216,6 +216,125 @@ fn xpath_find_string_values() {
   }}
 }
 
+#[test]
+/// Namespaces in scope at a node can be registered without naming them
+fn xpath_registers_namespaces_in_scope() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_file("tests/resources/simple_namespaces.xml")
+    .unwrap();
+  let root = doc.get_root_element().unwrap();
+  let context = Context::new(&doc).unwrap();
+  assert!(context.evaluate("//h:td").is_err());
+  assert!(context
+    .register_namespaces_in_scope(&root, &NamespaceRegistration::default())
+    .is_ok());
+  assert_eq!(
+    context.lookup_namespace("f"),
+    Some(String::from("http://example.com/ns/farewell"))
+  );
+  assert_eq!(context.evaluate("//h:td").unwrap().get_number_of_nodes(), 3);
+  assert_eq!(context.evaluate("//r:*").unwrap().get_number_of_nodes(), 1);
+}
+
+#[test]
+/// Default namespaces are only registered when a prefix is provided for them
+fn xpath_registers_default_namespace_prefix() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_string(r#"<root xmlns="urn:default"><item/><item/></root>"#)
+    .unwrap();
+  let context = Context::new(&doc).unwrap();
+  assert!(context
+    .register_document_namespaces(&NamespaceRegistration::default())
+    .is_ok());
+  assert_eq!(context.lookup_namespace("d"), None);
+
+  let options = NamespaceRegistration {
+    default_prefix: Some(String::from("d")),
+    ..NamespaceRegistration::default()
+  };
+  assert!(context.register_document_namespaces(&options).is_ok());
+  assert_eq!(context.evaluate("/d:root/d:item").unwrap().get_number_of_nodes(), 2);
+}
+
+#[test]
+/// A prefix bound to different URIs across the document follows the conflict rule
+fn xpath_document_namespace_conflicts() {
+  let parser = Parser::default();
+  let doc = parser
+    .parse_string(r#"<r><a xmlns:p="urn:one"><p:x/></a><b xmlns:p="urn:two"><p:y/></b></r>"#)
+    .unwrap();
+
+  let keep_first = Context::new(&doc).unwrap();
+  assert!(keep_first
+    .register_document_namespaces(&NamespaceRegistration::default())
+    .is_ok());
+  assert_eq!(keep_first.evaluate("//p:x").unwrap().get_number_of_nodes(), 1);
+  assert_eq!(keep_first.evaluate("//p:y").unwrap().get_number_of_nodes(), 0);
+
+  let replace = Context::new(&doc).unwrap();
+  let options = NamespaceRegistration {
+    conflict: NamespaceConflict::Replace,
+    ..NamespaceRegistration::default()
+  };
+  assert!(replace.register_document_namespaces(&options).is_ok());
+  assert_eq!(replace.evaluate("//p:x").unwrap().get_number_of_nodes(), 0);
+  assert_eq!(replace.evaluate("//p:y").unwrap().get_number_of_nodes(), 1);
+
+  // explicit registrations count as earlier bindings
+  let explicit = Context::new(&doc).unwrap();
+  explicit.register_namespace("p", "urn:two").unwrap();
+  assert!(explicit
+    .register_document_namespaces(&NamespaceRegistration::default())
+    .is_ok());
+  assert_eq!(explicit.lookup_namespace("p"), Some(String::from("urn:two")));
+
+  let strict = Context::new(&doc).unwrap();
+  let options = NamespaceRegistration {
+    conflict: NamespaceConflict::Error,
+    ..NamespaceRegistration::default()
+  };
+  assert!(strict.register_document_namespaces(&options).is_err());
+  assert_eq!(strict.lookup_namespace("p"), None);
+}
+
+#[test]
+/// XPointers resolve to node sets, with the libxml2 errors for malformed pointers
+fn xpointer_resolution() {
+  let parser = Parser::default();
+  let doc = parser.parse_file("tests/resources/ids.xml").unwrap();
+
+  let second_p = doc.xpointer("xpointer((//p)[2])").unwrap();
+  assert_eq!(second_p.get_number_of_nodes(), 1);
+  assert_eq!(
+    second_p.get_nodes_as_vec()[0].get_attribute("id"),
+    Some(String::from("mid"))
+  );
+
+  let child_sequence = doc.xpointer("element(/1/2/1)").unwrap();
+  assert_eq!(child_sequence.get_nodes_as_str(), vec!["Hello"]);
+  let by_id = doc.xpointer("end").unwrap();
+  assert_eq!(by_id.get_nodes_as_str(), vec!["World!"]);
+  let id_sequence = doc.xpointer("element(start)").unwrap();
+  assert_eq!(id_sequence.get_readonly_nodes_as_vec()[0].get_name(), "p");
+
+  let namespaced = doc
+    .xpointer("xmlns(x=urn:x) xpointer(//x:p) xpointer(//deeper)")
+    .unwrap();
+  assert_eq!(namespaced.get_number_of_nodes(), 2);
+
+  match doc.xpointer("xpointer(//p[)") {
+    Err(XPointerError::EvaluationFailed(errors)) => assert!(!errors.is_empty()),
+    other => panic!("malformed xpointer should fail, got {other:?}"),
+  }
+  assert!(doc.xpointer("element(/1/x)").is_err());
+  assert!(matches!(
+    doc.xpointer("xpointer(//p\0)"),
+    Err(XPointerError::InvalidPointer)
+  ));
+}
+
 /// Tests for checking xpath well-formedness
 mod compile_tests {
   use libxml::xpath::is_well_formed_xpath;
@@ -232,3 +351,82 @@ mod compile_tests {
     assert!(!compiles);
   }
 }
+
+#[test]
+/// Merging the results of several queries in document order
+fn document_order_of_merged_results() {
+  use libxml::readonly::RoNode;
+  use libxml::tree::Node;
+  use std::cmp::Ordering;
+
+  let parser = Parser::default();
+  let doc = parser
+    .parse_string("<r><a id='1'><b/></a><c id='2'/><d><e id='3'/></d></r>")
+    .unwrap();
+  let root = doc.get_root_element().unwrap();
+
+  let mut merged = root.findnodes("//e | //b").unwrap();
+  merged.extend(root.findnodes("//@id").unwrap());
+  merged.extend(root.findnodes("//a | //b").unwrap());
+  Node::sort_in_document_order(&mut merged);
+  let names: Vec<String> = merged.iter().map(|node| node.get_name()).collect();
+  assert_eq!(names, ["a", "id", "b", "id", "e", "id"]);
+
+  let a = &merged[0];
+  let b = &merged[2];
+  assert_eq!(a.cmp_document_order(b), Some(Ordering::Less));
+  assert_eq!(b.cmp_document_order(a), Some(Ordering::Greater));
+  assert_eq!(b.cmp_document_order(b), Some(Ordering::Equal));
+  assert_eq!(root.cmp_document_order(a), Some(Ordering::Less));
+
+  // nodes of different documents are not comparable, but still sort consistently
+  let other = parser.parse_string("<r/>").unwrap();
+  let other_root = other.get_root_element().unwrap();
+  assert_eq!(a.cmp_document_order(&other_root), None);
+  assert_eq!(
+    a.document_order(&other_root),
+    other_root.document_order(a).reverse()
+  );
+
+  let ro_root = doc.get_root_readonly().unwrap();
+  let mut ro_nodes = ro_root.findnodes("//e", &doc).unwrap();
+  ro_nodes.extend(ro_root.findnodes("//*", &doc).unwrap());
+  RoNode::sort_in_document_order(&mut ro_nodes);
+  let names: Vec<String> = ro_nodes.iter().map(|node| node.get_name()).collect();
+  assert_eq!(names, ["r", "a", "b", "c", "d", "e"]);
+}
+
+#[test]
+/// Namespace nodes are not part of the tree, but still sort consistently
+fn document_order_of_namespace_nodes() {
+  use libxml::readonly::RoNode;
+  use libxml::tree::NodeType;
+
+  let doc = Parser::default()
+    .parse_string("<r xmlns:a='urn:a'><c xmlns:b='urn:b'/></r>")
+    .unwrap();
+  let context = Context::new(&doc).unwrap();
+  // namespace nodes only live as long as the result
+  let result = context.evaluate("//namespace::* | //*").unwrap();
+  let mut nodes = result.get_readonly_nodes_as_vec();
+  nodes.reverse();
+  RoNode::sort_in_document_order(&mut nodes);
+
+  let is_namespace = |node: &RoNode| node.get_type() == Some(NodeType::NamespaceDecl);
+  assert_eq!(nodes.iter().filter(|node| is_namespace(node)).count(), 5);
+  let elements: Vec<String> = nodes
+    .iter()
+    .filter(|node| !is_namespace(node))
+    .map(|node| node.get_name())
+    .collect();
+  assert_eq!(elements, ["r", "c"]);
+
+  let namespace = *nodes.iter().find(|node| is_namespace(node)).unwrap();
+  let root = doc.get_root_readonly().unwrap();
+  assert_eq!(namespace.cmp_document_order(root), None);
+  assert_eq!(root.cmp_document_order(namespace), None);
+  assert_eq!(
+    namespace.document_order(root),
+    root.document_order(namespace).reverse()
+  );
+}